@@ -0,0 +1,167 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::sync::Mutex;
+
+// The number of PBKDF2 rounds used to hash passwords
+const HASH_ROUNDS: u32 = 100_000;
+
+// The shortest password an account may have, in characters
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+// A registered user; the password itself is never stored, only a salted hash of it
+#[derive(Clone, Serialize, Deserialize)]
+struct Account {
+    // The username as it was registered, used as the player's name at a table
+    username: String,
+    salt: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+// Every registered account, kept in a local JSON file that is rewritten whenever an account is
+// registered. Only tables requiring login open the store, and its client handler threads share it
+pub struct AccountStore {
+    path: String,
+
+    // Accounts by lowercase username so that usernames are unique ignoring case
+    accounts: HashMap<String, Account>,
+}
+
+impl AccountStore {
+    // Opens the store at the given path; a store that does not exist yet is created empty
+    pub fn open(path: &str) -> Result<AccountStore, String> {
+        let accounts = match fs::read(path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(accounts) => accounts,
+                Err(e) => return Err(format!("Could not read accounts {}; {}", path, e)),
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(format!("Could not open accounts {}; {}", path, e)),
+        };
+
+        Ok(AccountStore {
+            path: path.to_string(),
+            accounts: accounts,
+        })
+    }
+
+    // Writes the store to a temporary file then renames it over the previous store so that a crash
+    // while writing never loses the accounts
+    fn save(&self) -> Result<(), String> {
+        let bytes = match serde_json::to_vec_pretty(&self.accounts) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(format!("Could not serialize accounts; {}", e)),
+        };
+
+        let temporary_path = format!("{}.tmp", self.path);
+        match fs::write(&temporary_path, bytes)
+            .and_then(|_| fs::rename(&temporary_path, &self.path))
+        {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Could not save accounts {}; {}", self.path, e)),
+        }
+    }
+}
+
+// Whether or not the name is registered to an account in the store at the given path, so that a
+// table without logins can keep players from sitting under someone else's username
+pub fn is_registered(path: &str, name: &str) -> Result<bool, String> {
+    let store = AccountStore::open(path)?;
+    Ok(store.accounts.contains_key(&name.trim().to_lowercase()))
+}
+
+// Creates an account and saves the store; returns the reason the account could not be created
+// as an error. The password is hashed without holding the lock on the store, as hashing is slow
+// on purpose and every other login would wait on it
+pub fn register(
+    store: &Mutex<AccountStore>,
+    username: &str,
+    password: &str,
+) -> Result<String, String> {
+    let username = username.trim();
+    super::game::check_name(username)?;
+
+    if store
+        .lock()
+        .unwrap()
+        .accounts
+        .contains_key(&username.to_lowercase())
+    {
+        return Err("Username is already registered".to_string());
+    }
+
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        ));
+    }
+
+    let mut salt = vec![0 as u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let hash = hash_password(password, &salt);
+
+    // The username is checked again as another client may have registered it while hashing
+    let mut store = store.lock().unwrap();
+    if store.accounts.contains_key(&username.to_lowercase()) {
+        return Err("Username is already registered".to_string());
+    }
+    store.accounts.insert(
+        username.to_lowercase(),
+        Account {
+            username: username.to_string(),
+            salt: salt,
+            hash: hash,
+        },
+    );
+    if let Err(e) = store.save() {
+        store.accounts.remove(&username.to_lowercase());
+        println!("{}", e);
+        return Err("The account could not be saved".to_string());
+    }
+
+    Ok(username.to_string())
+}
+
+// Checks the password of an account; returns the username as it was registered or an error if
+// the account does not exist or the password is wrong. Like register, the password is hashed
+// without holding the lock on the store
+pub fn login(
+    store: &Mutex<AccountStore>,
+    username: &str,
+    password: &str,
+) -> Result<String, String> {
+    let account = store
+        .lock()
+        .unwrap()
+        .accounts
+        .get(&username.trim().to_lowercase())
+        .cloned();
+
+    match account {
+        Some(account) => {
+            let hash = hash_password(password, &account.salt);
+
+            // Compares every byte so that the time taken does not reveal how much matched
+            let difference = hash
+                .iter()
+                .zip(account.hash.iter())
+                .fold(0, |difference, (a, b)| difference | (a ^ b));
+
+            if difference == 0 && hash.len() == account.hash.len() {
+                Ok(account.username)
+            } else {
+                Err("Incorrect username or password".to_string())
+            }
+        }
+        None => Err("Incorrect username or password".to_string()),
+    }
+}
+
+fn hash_password(password: &str, salt: &[u8]) -> Vec<u8> {
+    let mut hash = vec![0 as u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password.as_bytes(), salt, HASH_ROUNDS, &mut hash);
+    hash
+}
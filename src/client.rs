@@ -1,69 +1,175 @@
 extern crate cribbage;
-use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
-use std::net;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 
-// Simply sends the message to the client and guarantees its arrival
+// Simply sends the message to the client and guarantees its arrival; returns an error if the
+// client can not be sent to
 fn simple_notification(
-    client_stream: &mut net::TcpStream,
+    connection: &mut super::connection::Connection,
     game_handler_transmitter: &mpsc::Sender<super::messages::ClientToGame>,
     message: super::messages::GameToClient,
-) {
-    client_stream
-        .write(&bincode::serialize(&message).unwrap())
-        .unwrap();
-    client_stream.flush().unwrap();
+) -> Result<(), String> {
+    connection.send(&message)?;
     game_handler_transmitter
         .send(super::messages::ClientToGame::TransmissionReceived)
         .unwrap();
+    Ok(())
+}
+
+// What a client handler needs to answer queries without the game model
+#[derive(Clone)]
+pub struct Queries {
+    // The statistics of every account, shared with the game thread
+    pub stats_store: Arc<Mutex<super::stats::StatsStore>>,
+
+    // The path of the tournament the table is part of, if any
+    pub tournament_path: Option<String>,
+
+    // The registered accounts, shared by every client handler, when the table requires login
+    pub accounts: Option<Arc<Mutex<super::accounts::AccountStore>>>,
+}
+
+// Receives a message from the client. Queries such as RequestStats, RequestLeaderboard and
+// RequestStandings are answered here directly, as the game model may be waiting on other players,
+// and are not returned. Returns an error when the client has disconnected
+fn receive_message(
+    connection: &mut super::connection::Connection,
+    queries: &Queries,
+) -> Result<Option<super::messages::ClientToGame>, String> {
+    loop {
+        match connection.receive()? {
+            Some(super::messages::ClientToGame::RequestStats { player }) => {
+                let stats = queries.stats_store.lock().unwrap().report(&player);
+                connection.send(&super::messages::GameToClient::Stats {
+                    player: player,
+                    stats: stats,
+                })?;
+            }
+            Some(super::messages::ClientToGame::RequestLeaderboard) => {
+                let leaderboard = queries.stats_store.lock().unwrap().leaderboard();
+                connection.send(&super::messages::GameToClient::Leaderboard(leaderboard))?;
+            }
+            Some(super::messages::ClientToGame::RequestStandings) => {
+                let standings = match &queries.tournament_path {
+                    Some(path) => match super::tournament::Tournament::load(path) {
+                        Ok(tournament) => Some(tournament.standings()),
+                        Err(e) => {
+                            println!("{}", e);
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                connection.send(&super::messages::GameToClient::Standings(standings))?;
+            }
+            // Only the client handler may say that a client has logged in
+            Some(super::messages::ClientToGame::LoggedIn(_)) => {}
+            message => return Ok(message),
+        }
+    }
+}
+
+// Checks a Login or Register against the account store. The password is hashed here rather than
+// by the game model so that a login does not hold up the rest of the table
+fn log_in(queries: &Queries, message: super::messages::ClientToGame) -> Result<String, String> {
+    let accounts = match &queries.accounts {
+        Some(accounts) => accounts,
+        None => return Err("This table does not use accounts".to_string()),
+    };
+
+    match message {
+        super::messages::ClientToGame::Login { username, password } => {
+            super::accounts::login(accounts, &username, &password)
+        }
+        super::messages::ClientToGame::Register { username, password } => {
+            super::accounts::register(accounts, &username, &password)
+        }
+        _ => Err("Input is not a login".to_string()),
+    }
 }
 
 // Polls for a Confirmation message from the client and forwards it to the game handler when it is
 // received
 fn confirmation_request(
-    client_stream: &mut net::TcpStream,
+    connection: &mut super::connection::Connection,
     game_handler_transmitter: &mpsc::Sender<super::messages::ClientToGame>,
+    queries: &Queries,
     message: super::messages::GameToClient,
-) {
-    let mut packet_from_client = [0 as u8; 256];
+) -> Result<(), String> {
     let mut has_sent_confirmation = false;
     while !has_sent_confirmation {
-        simple_notification(client_stream, game_handler_transmitter, message.clone());
-
-        client_stream.read(&mut packet_from_client).unwrap();
+        simple_notification(connection, game_handler_transmitter, message.clone())?;
 
-        let client_to_game: super::messages::ClientToGame =
-            bincode::deserialize(&packet_from_client).unwrap();
-
-        match client_to_game {
-            super::messages::ClientToGame::Confirmation => {
+        match receive_message(connection, queries)? {
+            Some(super::messages::ClientToGame::Confirmation) => {
                 game_handler_transmitter
                     .send(super::messages::ClientToGame::Confirmation)
                     .unwrap();
                 has_sent_confirmation = true;
             }
-            _ => {
-                packet_from_client = [0 as u8; 256];
-            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// Forwards the scores the client claims in a PlayScore to the game model
+fn forward_score(
+    connection: &mut super::connection::Connection,
+    game_handler_transmitter: &mpsc::Sender<super::messages::ClientToGame>,
+    queries: &Queries,
+) -> Result<(), String> {
+    let mut has_sent_score = false;
+    while !has_sent_score {
+        if let Some(super::messages::ClientToGame::PlayScore(scores)) =
+            receive_message(connection, queries)?
+        {
+            game_handler_transmitter
+                .send(super::messages::ClientToGame::PlayScore(scores))
+                .unwrap();
+            has_sent_score = true;
         }
     }
+
+    Ok(())
 }
 
 // Handles input and output to each client
 pub fn handle_client(
-    // The TCP stream the handler takes for the client given when spawning the thread
-    mut client_stream: net::TcpStream,
+    // The connection to the client given when spawning the thread
+    connection: super::connection::Connection,
     // The transmitter used to send messages to the game thread; shared with the other clients
     game_handler_transmitter: mpsc::Sender<super::messages::ClientToGame>,
     game_handler_receiver: mpsc::Receiver<super::messages::GameToClient>,
+    // The shared state used to answer queries from the client
+    queries: Queries,
 ) {
-    let mut is_disconncted = false;
-
     game_handler_transmitter
         .send(super::messages::ClientToGame::Greeting)
         .unwrap();
 
+    // When the client disconnects the thread ends, which the game model sees as the client's
+    // receiver closing
+    if let Err(e) = forward_messages(
+        connection,
+        &game_handler_transmitter,
+        &game_handler_receiver,
+        &queries,
+    ) {
+        println!("Client disconnected; {}", e);
+    }
+}
+
+// Forwards messages from the game model to the client and the client's responses back until the
+// game model disconnects the client; returns an error if the client disconnects first
+fn forward_messages(
+    mut connection: super::connection::Connection,
+    game_handler_transmitter: &mpsc::Sender<super::messages::ClientToGame>,
+    game_handler_receiver: &mpsc::Receiver<super::messages::GameToClient>,
+    queries: &Queries,
+) -> Result<(), String> {
+    let mut is_disconncted = false;
+
     // While the connection is accepted
     while !is_disconncted {
         // Forward message from receiver to the client then wait for client response
@@ -72,259 +178,700 @@ pub fn handle_client(
             // denied
             Ok(super::messages::GameToClient::DeniedTableFull) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::DeniedTableFull,
-                );
+                )?;
             }
 
-            // Accepts the player's name
-            // TODO Confirm name is not already in use
+            // Accepts the player's name, or the name and reconnect token of a player taking their
+            // seat back at a restored table; the game model checks that it is valid and not in use
+            // and responds with a NameRejected and another WaitName if it is not
             Ok(super::messages::GameToClient::WaitName) => {
-                let mut packet_from_client = [0 as u8; 256];
                 let mut valid_name = false;
                 while !valid_name {
                     simple_notification(
-                        &mut client_stream,
-                        &game_handler_transmitter,
+                        &mut connection,
+                        game_handler_transmitter,
                         super::messages::GameToClient::WaitName,
-                    );
-                    client_stream.read(&mut packet_from_client).unwrap();
-                    let client_to_game: super::messages::ClientToGame =
-                        bincode::deserialize(&packet_from_client).unwrap();
-                    match client_to_game {
-                        super::messages::ClientToGame::Name(name) => {
+                    )?;
+                    match receive_message(&mut connection, queries)? {
+                        Some(super::messages::ClientToGame::Name(name)) => {
                             valid_name = true;
                             game_handler_transmitter
                                 .send(super::messages::ClientToGame::Name(name))
                                 .unwrap();
                         }
-                        _ => {
-                            packet_from_client = [0 as u8; 256];
+                        Some(reconnect @ super::messages::ClientToGame::Reconnect { .. }) => {
+                            valid_name = true;
+                            game_handler_transmitter.send(reconnect).unwrap();
+                        }
+                        _ => {}
+                    };
+                }
+            }
+
+            // Accepts the player's login or registration when the table requires an account. A
+            // failed login is answered here, without troubling the game model, until one succeeds
+            Ok(super::messages::GameToClient::WaitLogin) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitLogin,
+                )?;
+                loop {
+                    match receive_message(&mut connection, queries)? {
+                        Some(login @ super::messages::ClientToGame::Login { .. })
+                        | Some(login @ super::messages::ClientToGame::Register { .. }) => {
+                            match log_in(queries, login) {
+                                Ok(username) => {
+                                    game_handler_transmitter
+                                        .send(super::messages::ClientToGame::LoggedIn(username))
+                                        .unwrap();
+                                    break;
+                                }
+                                Err(reason) => {
+                                    connection.send(
+                                        &super::messages::GameToClient::LoginRejected(reason),
+                                    )?;
+                                    connection.send(&super::messages::GameToClient::WaitLogin)?;
+                                }
+                            }
                         }
+                        _ => {}
                     };
                 }
             }
 
+            Ok(super::messages::GameToClient::LoginRejected(reason)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::LoginRejected(reason),
+            )?,
+
+            Ok(super::messages::GameToClient::NameRejected(reason)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::NameRejected(reason),
+            )?,
+
             Ok(super::messages::GameToClient::PlayerJoinNotification { name, number, of }) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::PlayerJoinNotification { name, number, of },
-                )
+                )?
             }
 
+            Ok(super::messages::GameToClient::ReconnectToken(token)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::ReconnectToken(token),
+            )?,
+
+            Ok(super::messages::GameToClient::SeatRoster(roster)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::SeatRoster(roster),
+            )?,
+
+            Ok(super::messages::GameToClient::Teams(teams)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::Teams(teams),
+            )?,
+
+            Ok(super::messages::GameToClient::TableRules {
+                points_to_win,
+                skunk_line,
+                captain,
+                captain_points_to_win,
+                lowball,
+                five_card,
+                auction,
+                hints,
+            }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::TableRules {
+                    points_to_win,
+                    skunk_line,
+                    captain,
+                    captain_points_to_win,
+                    lowball,
+                    five_card,
+                    auction,
+                    hints,
+                },
+            )?,
+
+            Ok(super::messages::GameToClient::ScoreUpdate(scores)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::ScoreUpdate(scores),
+            )?,
+
+            Ok(super::messages::GameToClient::GameWinner(player)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::GameWinner(player),
+            )?,
+
+            Ok(super::messages::GameToClient::MatchScore(scores)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::MatchScore(scores),
+            )?,
+
+            Ok(super::messages::GameToClient::MatchWinner(player)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::MatchWinner(player),
+            )?,
+
+            Ok(super::messages::GameToClient::NewGame { number, dealer }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::NewGame { number, dealer },
+            )?,
+
             Ok(super::messages::GameToClient::WaitInitialCut) => confirmation_request(
-                &mut client_stream,
-                &game_handler_transmitter,
+                &mut connection,
+                game_handler_transmitter,
+                queries,
                 super::messages::GameToClient::WaitInitialCut,
-            ),
+            )?,
 
-            Ok(super::messages::GameToClient::InitialCutResult { name, card }) => {
+            Ok(super::messages::GameToClient::InitialCutResult { player, card }) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
-                    super::messages::GameToClient::InitialCutResult { name, card },
-                );
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::InitialCutResult { player, card },
+                )?;
             }
 
-            Ok(super::messages::GameToClient::InitialCutSuccess(name)) => {
+            Ok(super::messages::GameToClient::InitialCutSuccess(player)) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
-                    super::messages::GameToClient::InitialCutSuccess(name),
-                );
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::InitialCutSuccess(player),
+                )?;
             }
 
             Ok(super::messages::GameToClient::InitialCutFailure) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::InitialCutFailure,
-                );
+                )?;
             }
 
             Ok(super::messages::GameToClient::WaitDeal) => {
                 confirmation_request(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
+                    queries,
                     super::messages::GameToClient::WaitDeal,
-                );
+                )?;
             }
 
             Ok(super::messages::GameToClient::Dealing) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::Dealing,
-                );
+                )?;
             }
 
+            Ok(super::messages::GameToClient::KittyDealt(count)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::KittyDealt(count),
+            )?,
+
             Ok(super::messages::GameToClient::DealtHand(hand)) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::DealtHand(hand),
-                );
+                )?;
             }
 
-            Ok(super::messages::GameToClient::WaitDiscardOne) => {
-                let mut packet_from_client = [0 as u8; 256];
+            Ok(super::messages::GameToClient::WaitBid) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitBid,
+                )?;
+
+                // Check for input from the client and forward BidPlaced messages until the client
+                // bids; a rejected bid is followed by another WaitBid
+                connection.set_nonblocking(true);
+
+                loop {
+                    if let Some(super::messages::ClientToGame::Bid(bid)) =
+                        receive_message(&mut connection, queries)?
+                    {
+                        game_handler_transmitter
+                            .send(super::messages::ClientToGame::Bid(bid))
+                            .unwrap();
+                        break;
+                    }
+                    match game_handler_receiver.try_recv() {
+                        Ok(super::messages::GameToClient::BidPlaced(player)) => {
+                            simple_notification(
+                                &mut connection,
+                                game_handler_transmitter,
+                                super::messages::GameToClient::BidPlaced(player),
+                            )?
+                        }
+                        Ok(_) => println!(
+                            "Invalid message to client when trying to receive a BidPlaced message"
+                        ),
+                        _ => {}
+                    };
+                }
 
+                connection.set_nonblocking(false);
+            }
+
+            Ok(super::messages::GameToClient::BidRejected(reason)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::BidRejected(reason),
+            )?,
+
+            Ok(super::messages::GameToClient::BidPlaced(player)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::BidPlaced(player),
+            )?,
+
+            Ok(super::messages::GameToClient::AuctionResult { player, bid }) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::AuctionResult { player, bid },
+                )?
+            }
+
+            Ok(super::messages::GameToClient::WaitDiscardOne) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::WaitDiscardOne,
-                );
+                )?;
 
-                // Check for input from the client and forward DiscardPlaced messages
-                client_stream.set_nonblocking(true).unwrap();
+                // Check for input from the client and forward DiscardPlaced and hint messages; the
+                // answer to the discard, an Error and another request if it is rejected, is
+                // forwarded once the discard has been sent
+                connection.set_nonblocking(true);
 
                 let mut received_discard_message = false;
                 while !received_discard_message {
-                    match client_stream.read(&mut packet_from_client) {
-                        Ok(_) => {
-                            let client_to_game: super::messages::ClientToGame =
-                                bincode::deserialize(&packet_from_client).unwrap();
-                            match client_to_game {
-                                super::messages::ClientToGame::DiscardOne { index } => {
-                                    game_handler_transmitter
-                                        .send(super::messages::ClientToGame::DiscardOne { index })
-                                        .unwrap();
-                                    received_discard_message = true;
-                                    packet_from_client = [0 as u8; 256];
-                                }
-                                _ => {
-                                    packet_from_client = [0 as u8; 256];
-                                }
-                            }
+                    match receive_message(&mut connection, queries)? {
+                        Some(super::messages::ClientToGame::DiscardOne { index }) => {
+                            game_handler_transmitter
+                                .send(super::messages::ClientToGame::DiscardOne { index })
+                                .unwrap();
+                            received_discard_message = true;
+                            continue;
+                        }
+                        Some(super::messages::ClientToGame::RequestHint) => {
+                            game_handler_transmitter
+                                .send(super::messages::ClientToGame::RequestHint)
+                                .unwrap();
                         }
                         _ => {}
                     };
                     match game_handler_receiver.try_recv() {
-                        Ok(super::messages::GameToClient::DiscardPlacedOne(name)) => simple_notification(
-                            &mut client_stream,
-                            &game_handler_transmitter,
-                            super::messages::GameToClient::DiscardPlacedOne(name),
-                        ),
+                        Ok(super::messages::GameToClient::DiscardPlacedOne(player)) => simple_notification(
+                            &mut connection,
+                            game_handler_transmitter,
+                            super::messages::GameToClient::DiscardPlacedOne(player),
+                        )?,
+                        Ok(message @ super::messages::GameToClient::DiscardHints(_))
+                        | Ok(message @ super::messages::GameToClient::HintDenied(_))
+                        | Ok(message @ super::messages::GameToClient::HintUsed(_))
+                        | Ok(message @ super::messages::GameToClient::Error(_)) => {
+                            simple_notification(
+                                &mut connection,
+                                game_handler_transmitter,
+                                message,
+                            )?
+                        }
                         Ok(_) => println!("Invalid message to client when trying to receive a DiscardPlacedOne message"),
                         _ => {},
                     };
                 }
 
-                client_stream.set_nonblocking(false).unwrap();
+                connection.set_nonblocking(false);
             }
             Ok(super::messages::GameToClient::WaitDiscardTwo) => {
-                let mut packet_from_client = [0 as u8; 256];
-
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::WaitDiscardTwo,
-                );
+                )?;
 
-                // Check for input from the client and forward DiscardPlaced messages
-                client_stream.set_nonblocking(true).unwrap();
+                // Check for input from the client and forward DiscardPlaced and hint messages; the
+                // answer to the discard, an Error and another request if it is rejected, is
+                // forwarded once the discard has been sent
+                connection.set_nonblocking(true);
 
                 let mut received_discard_message = false;
                 while !received_discard_message {
-                    match client_stream.read(&mut packet_from_client) {
-                        Ok(_) => {
-                            let client_to_game: super::messages::ClientToGame =
-                                bincode::deserialize(&packet_from_client).unwrap();
-                            match client_to_game {
-                                super::messages::ClientToGame::DiscardTwo {
+                    match receive_message(&mut connection, queries)? {
+                        Some(super::messages::ClientToGame::DiscardTwo {
+                            index_one,
+                            index_two,
+                        }) => {
+                            game_handler_transmitter
+                                .send(super::messages::ClientToGame::DiscardTwo {
                                     index_one,
                                     index_two,
-                                } => {
-                                    game_handler_transmitter
-                                        .send(super::messages::ClientToGame::DiscardTwo {
-                                            index_one,
-                                            index_two,
-                                        })
-                                        .unwrap();
-                                    received_discard_message = true;
-                                    packet_from_client = [0 as u8; 256];
-                                }
-                                _ => {
-                                    packet_from_client = [0 as u8; 256];
-                                }
-                            }
+                                })
+                                .unwrap();
+                            received_discard_message = true;
+                            continue;
+                        }
+                        Some(super::messages::ClientToGame::RequestHint) => {
+                            game_handler_transmitter
+                                .send(super::messages::ClientToGame::RequestHint)
+                                .unwrap();
                         }
                         _ => {}
                     };
                     match game_handler_receiver.try_recv() {
-                        Ok(super::messages::GameToClient::DiscardPlacedTwo(name)) => simple_notification(
-                            &mut client_stream,
-                            &game_handler_transmitter,
-                            super::messages::GameToClient::DiscardPlacedTwo(name),
-                        ),
+                        Ok(super::messages::GameToClient::DiscardPlacedTwo(player)) => simple_notification(
+                            &mut connection,
+                            game_handler_transmitter,
+                            super::messages::GameToClient::DiscardPlacedTwo(player),
+                        )?,
+                        Ok(message @ super::messages::GameToClient::DiscardHints(_))
+                        | Ok(message @ super::messages::GameToClient::HintDenied(_))
+                        | Ok(message @ super::messages::GameToClient::HintUsed(_))
+                        | Ok(message @ super::messages::GameToClient::Error(_)) => {
+                            simple_notification(
+                                &mut connection,
+                                game_handler_transmitter,
+                                message,
+                            )?
+                        }
                         Ok(_) => println!("Invalid message to client when trying to receive a DiscardPlacedTwo message"),
                         _ => {},
                     };
                 }
 
-                client_stream.set_nonblocking(false).unwrap();
+                connection.set_nonblocking(false);
             }
 
-            Ok(super::messages::GameToClient::DiscardPlacedOne(name)) => simple_notification(
-                &mut client_stream,
-                &game_handler_transmitter,
-                super::messages::GameToClient::DiscardPlacedOne(name),
-            ),
-            Ok(super::messages::GameToClient::DiscardPlacedTwo(name)) => simple_notification(
-                &mut client_stream,
-                &game_handler_transmitter,
-                super::messages::GameToClient::DiscardPlacedTwo(name),
-            ),
+            Ok(super::messages::GameToClient::DiscardPlacedOne(player)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::DiscardPlacedOne(player),
+            )?,
+            Ok(super::messages::GameToClient::DiscardPlacedTwo(player)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::DiscardPlacedTwo(player),
+            )?,
+
+            Ok(super::messages::GameToClient::HintUsed(player)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::HintUsed(player),
+            )?,
 
             Ok(super::messages::GameToClient::AllDiscards) => {
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::AllDiscards,
-                );
+                )?;
             }
 
-            Ok(super::messages::GameToClient::CutStarter(name, card)) => {
+            Ok(super::messages::GameToClient::CutStarter(player, card)) => {
                 println!("Sending CutStarter");
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
-                    super::messages::GameToClient::CutStarter(name, card),
-                );
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::CutStarter(player, card),
+                )?;
                 println!("Sent CutStarter");
             }
 
             Ok(super::messages::GameToClient::WaitCutStarter) => {
                 confirmation_request(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
+                    queries,
                     super::messages::GameToClient::WaitCutStarter,
-                );
+                )?;
             }
 
+            Ok(super::messages::GameToClient::Nibs) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::Nibs,
+            )?,
+
+            // Forward whether the dealer calls nibs or not
+            Ok(super::messages::GameToClient::WaitNibs) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitNibs,
+                )?;
+
+                let mut has_sent_call = false;
+                while !has_sent_call {
+                    match receive_message(&mut connection, queries)? {
+                        Some(super::messages::ClientToGame::Confirmation) => {
+                            game_handler_transmitter
+                                .send(super::messages::ClientToGame::Confirmation)
+                                .unwrap();
+                            has_sent_call = true;
+                        }
+                        Some(super::messages::ClientToGame::Denial) => {
+                            game_handler_transmitter
+                                .send(super::messages::ClientToGame::Denial)
+                                .unwrap();
+                            has_sent_call = true;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            // Forward the card the player plays; the game model answers a card that can not be
+            // played with an Error and another WaitPlay
+            Ok(super::messages::GameToClient::WaitPlay(playable)) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitPlay(playable),
+                )?;
+
+                let mut has_sent_play = false;
+                while !has_sent_play {
+                    if let Some(super::messages::ClientToGame::PlayTurn(index)) =
+                        receive_message(&mut connection, queries)?
+                    {
+                        game_handler_transmitter
+                            .send(super::messages::ClientToGame::PlayTurn(index))
+                            .unwrap();
+                        has_sent_play = true;
+                    }
+                }
+            }
+
+            Ok(super::messages::GameToClient::CardPlayed {
+                player,
+                card,
+                points,
+            }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::CardPlayed {
+                    player,
+                    card,
+                    points,
+                },
+            )?,
+
+            Ok(super::messages::GameToClient::Go { player, points }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::Go { player, points },
+            )?,
+
+            Ok(super::messages::GameToClient::ShowHand {
+                player,
+                hand,
+                scores,
+            }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::ShowHand {
+                    player,
+                    hand,
+                    scores,
+                },
+            )?,
+
+            Ok(super::messages::GameToClient::ShowCrib {
+                player,
+                crib,
+                scores,
+            }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::ShowCrib {
+                    player,
+                    crib,
+                    scores,
+                },
+            )?,
+
+            // Forward the scores the player claims for the card they played or the hand they
+            // count; the game model answers a claim it does not accept with an
+            // InvalidPlayScoring or IncompletePlayScoring and asks again
+            Ok(super::messages::GameToClient::WaitPlayScore) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitPlayScore,
+                )?;
+                forward_score(&mut connection, game_handler_transmitter, queries)?;
+            }
+
+            Ok(super::messages::GameToClient::WaitShowScore { cards, is_crib }) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitShowScore { cards, is_crib },
+                )?;
+                forward_score(&mut connection, game_handler_transmitter, queries)?;
+            }
+
+            Ok(super::messages::GameToClient::InvalidPlayScoring) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::InvalidPlayScoring,
+            )?,
+
+            Ok(super::messages::GameToClient::IncompletePlayScoring) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::IncompletePlayScoring,
+            )?,
+
+            Ok(super::messages::GameToClient::Muggins { player, points }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::Muggins { player, points },
+            )?,
+
+            Ok(super::messages::GameToClient::WaitPlacement(card)) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitPlacement(card),
+                )?;
+
+                // Forward the square the card is placed in; the practice table answers with a
+                // PlacementRejected and another WaitPlacement if it can not go there
+                let mut has_sent_placement = false;
+                while !has_sent_placement {
+                    if let Some(super::messages::ClientToGame::PlaceCard { row, column }) =
+                        receive_message(&mut connection, queries)?
+                    {
+                        game_handler_transmitter
+                            .send(super::messages::ClientToGame::PlaceCard { row, column })
+                            .unwrap();
+                        has_sent_placement = true;
+                    }
+                }
+            }
+
+            Ok(super::messages::GameToClient::PlacementRejected(reason)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::PlacementRejected(reason),
+            )?,
+
+            Ok(super::messages::GameToClient::SquaresResult {
+                grid,
+                starter,
+                rows,
+                columns,
+                total,
+            }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::SquaresResult {
+                    grid,
+                    starter,
+                    rows,
+                    columns,
+                    total,
+                },
+            )?,
+
+            Ok(super::messages::GameToClient::WaitNewGrid) => confirmation_request(
+                &mut connection,
+                game_handler_transmitter,
+                queries,
+                super::messages::GameToClient::WaitNewGrid,
+            )?,
+
+            Ok(super::messages::GameToClient::WaitHandCount { hand, starter }) => {
+                simple_notification(
+                    &mut connection,
+                    game_handler_transmitter,
+                    super::messages::GameToClient::WaitHandCount { hand, starter },
+                )?;
+
+                // Forward the scores the player claims for the hand
+                let mut has_sent_count = false;
+                while !has_sent_count {
+                    if let Some(super::messages::ClientToGame::PlayScore(scores)) =
+                        receive_message(&mut connection, queries)?
+                    {
+                        game_handler_transmitter
+                            .send(super::messages::ClientToGame::PlayScore(scores))
+                            .unwrap();
+                        has_sent_count = true;
+                    }
+                }
+            }
+
+            Ok(super::messages::GameToClient::HandCountResult {
+                right,
+                wrong,
+                missed,
+                accuracy,
+            }) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::HandCountResult {
+                    right,
+                    wrong,
+                    missed,
+                    accuracy,
+                },
+            )?,
+
+            Ok(super::messages::GameToClient::Error(reason)) => simple_notification(
+                &mut connection,
+                game_handler_transmitter,
+                super::messages::GameToClient::Error(reason),
+            )?,
+
             Ok(super::messages::GameToClient::Disconnect) => {
                 is_disconncted = true;
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::Disconnect,
-                );
+                )?;
             }
 
             _ => {
                 println!("Unexpected message from game handler");
                 simple_notification(
-                    &mut client_stream,
-                    &game_handler_transmitter,
+                    &mut connection,
+                    game_handler_transmitter,
                     super::messages::GameToClient::Error(String::from(
                         "Unexpected message from game handler",
                     )),
-                );
+                )?;
 
                 is_disconncted = true;
             }
         }
     }
+
+    Ok(())
 }
@@ -0,0 +1,465 @@
+use serde::{Deserialize, Serialize};
+use std::str;
+use std::time;
+
+// The seat the captain sits in in Captain's cribbage; whoever takes the first seat
+const CAPTAIN_SEAT: u8 = 0;
+
+// The settings of a table given on the command line; saved in every snapshot so that a table can
+// be restored with the same settings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TableConfig {
+    // The port the table listens on
+    pub port: String,
+
+    // The port the table listens on for WebSocket connections from browser clients, if any
+    pub websocket_port: Option<String>,
+
+    // The paths of the PEM encoded certificate and private key used to encrypt connections with
+    // TLS; connections are plaintext unless both are given
+    pub tls_certificate: Option<String>,
+    pub tls_key: Option<String>,
+
+    // The number of players to wait for
+    pub num_players: u8,
+
+    // The number of teams of partners the players are split into, if any; the player in seat s is
+    // on team s % teams so that the seating alternates between teams
+    pub teams: Option<u8>,
+
+    // The scoring options given to the game object
+    pub man_scoring: bool,
+    pub underpegging: bool,
+    pub muggins: bool,
+    pub overpegging: bool,
+
+    // The number of points needed to win a game; 121 for a standard game and 61 for a short one
+    pub points_to_win: u8,
+
+    // Whether or not the game is old-style five-card cribbage, where two players are dealt five
+    // cards, the non-dealer starts with three points, and the game is played to 61 by default
+    pub five_card: bool,
+
+    // Whether or not the game is Auction cribbage, where the players bid points for the crib after
+    // every deal
+    pub auction: bool,
+
+    // Whether or not the game is Captain's cribbage, where the player in the first seat plays as
+    // the captain against the other two players as a team, and the points the captain needs to
+    // win; the team needs the points to win
+    pub captain: bool,
+    pub captain_points_to_win: u8,
+
+    // Whether or not the game is Lowball, where points are bad and the side that reaches the points
+    // to win loses
+    pub lowball: bool,
+
+    // Whether or not players may ask for discard hints; never allowed at ranked tables
+    pub hints: bool,
+
+    // The solo practice mode the table is opened in instead of a game, if any
+    pub practice: Option<super::practice::PracticeMode>,
+
+    // How the decks are ordered
+    pub deck_setup: super::deck::DeckSetup,
+
+    // Whether or not players must log in to an account to sit at the table, and the path of the
+    // file the accounts are stored in
+    pub require_login: bool,
+    pub accounts_path: String,
+
+    // Whether or not the table is ranked; ranked tables always use the standard rules, require
+    // every player to log in, and update the ratings of the players when the game ends
+    pub ranked: bool,
+
+    // The number of games the table plays as a match, and whether or not a skunk counts as two
+    // game wins in the match
+    pub match_games: u8,
+    pub skunk_counts_double: bool,
+
+    // The usernames of the only accounts that may sit at the table; anyone may sit when empty
+    pub reserved_seats: Vec<String>,
+
+    // The path the winner of the match is written to when it is decided, and the path of the
+    // tournament the table is part of, if any
+    pub result_path: Option<String>,
+    pub tournament_path: Option<String>,
+
+    // The path of the file the statistics of every account are stored in
+    pub stats_path: String,
+
+    // The path of the append-only log of every event and broadcast at the table
+    pub log_path: String,
+
+    // The path the table state is periodically saved to
+    pub snapshot_path: String,
+}
+
+impl TableConfig {
+    // The number of sides playing against each other; the number of teams when players are
+    // partnered and otherwise the number of players
+    pub fn num_sides(&self) -> u8 {
+        if self.captain {
+            2
+        } else {
+            self.teams.unwrap_or(self.num_players)
+        }
+    }
+
+    // Whether or not any players are partnered, either in teams or as the team playing against the
+    // captain
+    pub fn has_partners(&self) -> bool {
+        self.teams.is_some() || self.captain
+    }
+
+    // The score a loser must reach to avoid being skunked; 30 points short of the points to win
+    // for standard and short games, and half the points to win for shorter practice games
+    pub fn skunk_line(&self) -> u8 {
+        skunk_line_for_points(self.points_to_win)
+    }
+
+    // The points the given side needs to win; only the captain needs a different number of points
+    // from everyone else
+    pub fn points_to_win_of(&self, side: u8) -> u8 {
+        if self.captain && side == 0 {
+            self.captain_points_to_win
+        } else {
+            self.points_to_win
+        }
+    }
+
+    // The score the given side must reach to avoid being skunked, from the points it needs to win
+    pub fn skunk_line_of(&self, side: u8) -> u8 {
+        skunk_line_for_points(self.points_to_win_of(side))
+    }
+
+    // The seat of the captain in Captain's cribbage, or None in every other game
+    pub fn captain_seat(&self) -> Option<u8> {
+        if self.captain {
+            Some(CAPTAIN_SEAT)
+        } else {
+            None
+        }
+    }
+
+    // The side the player in the given seat plays for; in Captain's cribbage the captain is side 0
+    // and the team side 1
+    pub fn side_of(&self, seat: u8) -> u8 {
+        match self.teams {
+            _ if self.captain => (seat != CAPTAIN_SEAT) as u8,
+            Some(teams) => seat % teams,
+            None => seat,
+        }
+    }
+
+    // The config given by the command line arguments for a table of the given number of players
+    // followed by the given optional arguments
+    #[cfg(test)]
+    pub fn from_test_args(num_players: u8, optional: &str) -> TableConfig {
+        let mut args: Vec<String> = vec!["cribbage_server", "1025"]
+            .into_iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        args.push(num_players.to_string());
+        args.extend(vec!["false".to_string(); 4]);
+        args.extend(optional.split_whitespace().map(|arg| arg.to_string()));
+        TableConfig::from_args(&args)
+    }
+
+    // Command line arguments are a port number, the number of players to wait for, and whether or
+    // not manual scoring, underscoring, muggins, and overscoring are enabled in that order (sample
+    // input is "executable_name 1025 2 false false false false")
+    //
+    // Optional arguments of the form key=value may follow; "seed=N" shuffles every deck from the
+    // given seed and "deck=path" takes decks in order from a file of stacked decks so that a
+    // specific game can be reproduced, "log=path" sets where the game log is written,
+    // "snapshot=path" sets where the table state is saved, "websocket=port" also accepts WebSocket
+    // connections on the given port, "cert=path" with "key=path" encrypt every connection with TLS,
+    // "login=true" requires players to log in to an account to sit, "accounts=path" sets where
+    // accounts are stored, "stats=path" sets where the statistics of accounts are stored,
+    // "ranked=true" makes the table ranked, "games=N" plays a best of N match, "skunkdouble=true"
+    // counts a skunk as two game wins in the match, "seats=a,b" only lets the listed accounts sit
+    // and needs "login=true", "result=path" sets where the winner is written when the match is
+    // decided, "tournament=path" sets the tournament whose standings players may ask for, and
+    // "teams=N" splits the players into N teams of partners, "points=N" sets the points needed to
+    // win a game, "lowball=true" plays Lowball, "fivecard=true" plays five-card cribbage,
+    // "auction=true" plays Auction cribbage, "practice=squares" or "practice=counting" opens a one
+    // player Cribbage Squares or counting practice table, "captain=true" plays Captain's cribbage
+    // with "captainpoints=N" setting the points the captain needs, and "hints=true" lets players
+    // ask for discard hints
+    pub fn from_args(args: &Vec<String>) -> TableConfig {
+        let port = args[1].trim().to_string();
+
+        // Parses the number of players to wait for
+        let num_players: u8 = args[2].trim().parse().unwrap();
+
+        // Parses the boolean options given in the command line arguments
+        let man_scoring: bool = str::FromStr::from_str(&args[3]).unwrap();
+        let underpegging: bool = str::FromStr::from_str(&args[4]).unwrap();
+        let muggins: bool = str::FromStr::from_str(&args[5]).unwrap();
+        let overpegging: bool = str::FromStr::from_str(&args[6]).unwrap();
+
+        // Parses the optional arguments
+        let mut seed: Option<u64> = None;
+        let mut deck_path: Option<String> = None;
+        let mut log_path: Option<String> = None;
+        let mut snapshot_path: Option<String> = None;
+        let mut websocket_port: Option<String> = None;
+        let mut tls_certificate: Option<String> = None;
+        let mut tls_key: Option<String> = None;
+        let mut require_login = false;
+        let mut accounts_path = "accounts.json".to_string();
+        let mut stats_path = "stats.json".to_string();
+        let mut ranked = false;
+        let mut match_games: u8 = 1;
+        let mut skunk_counts_double = false;
+        let mut reserved_seats: Vec<String> = Vec::new();
+        let mut result_path: Option<String> = None;
+        let mut tournament_path: Option<String> = None;
+        let mut teams: Option<u8> = None;
+        let mut points_to_win: Option<u8> = None;
+        let mut lowball = false;
+        let mut five_card = false;
+        let mut auction = false;
+        let mut practice: Option<super::practice::PracticeMode> = None;
+        let mut captain = false;
+        let mut hints = false;
+        let mut captain_points_to_win: Option<u8> = None;
+        for arg in &args[7..] {
+            match arg.find('=') {
+                Some(split) if &arg[..split] == "seed" => {
+                    seed = Some(arg[split + 1..].trim().parse().unwrap());
+                }
+                Some(split) if &arg[..split] == "deck" => {
+                    deck_path = Some(arg[split + 1..].to_string());
+                }
+                Some(split) if &arg[..split] == "log" => {
+                    log_path = Some(arg[split + 1..].to_string());
+                }
+                Some(split) if &arg[..split] == "snapshot" => {
+                    snapshot_path = Some(arg[split + 1..].to_string());
+                }
+                Some(split) if &arg[..split] == "websocket" => {
+                    websocket_port = Some(arg[split + 1..].trim().to_string());
+                }
+                Some(split) if &arg[..split] == "cert" => {
+                    tls_certificate = Some(arg[split + 1..].to_string());
+                }
+                Some(split) if &arg[..split] == "key" => {
+                    tls_key = Some(arg[split + 1..].to_string());
+                }
+                Some(split) if &arg[..split] == "login" => {
+                    require_login = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                Some(split) if &arg[..split] == "accounts" => {
+                    accounts_path = arg[split + 1..].to_string();
+                }
+                Some(split) if &arg[..split] == "stats" => {
+                    stats_path = arg[split + 1..].to_string();
+                }
+                Some(split) if &arg[..split] == "ranked" => {
+                    ranked = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                Some(split) if &arg[..split] == "games" => {
+                    match_games = arg[split + 1..].trim().parse().unwrap();
+                }
+                Some(split) if &arg[..split] == "skunkdouble" => {
+                    skunk_counts_double = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                Some(split) if &arg[..split] == "seats" => {
+                    reserved_seats = arg[split + 1..]
+                        .split(',')
+                        .map(|name| name.trim().to_string())
+                        .filter(|name| !name.is_empty())
+                        .collect();
+                }
+                Some(split) if &arg[..split] == "result" => {
+                    result_path = Some(arg[split + 1..].to_string());
+                }
+                Some(split) if &arg[..split] == "tournament" => {
+                    tournament_path = Some(arg[split + 1..].to_string());
+                }
+                Some(split) if &arg[..split] == "teams" => {
+                    teams = Some(arg[split + 1..].trim().parse().unwrap());
+                }
+                Some(split) if &arg[..split] == "points" => {
+                    points_to_win = Some(arg[split + 1..].trim().parse().unwrap());
+                }
+                Some(split) if &arg[..split] == "lowball" => {
+                    lowball = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                Some(split) if &arg[..split] == "fivecard" => {
+                    five_card = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                Some(split) if &arg[..split] == "auction" => {
+                    auction = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                Some(split) if &arg[..split] == "practice" => {
+                    practice =
+                        Some(super::practice::PracticeMode::parse(&arg[split + 1..]).unwrap());
+                }
+                Some(split) if &arg[..split] == "captain" => {
+                    captain = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                Some(split) if &arg[..split] == "captainpoints" => {
+                    captain_points_to_win = Some(arg[split + 1..].trim().parse().unwrap());
+                }
+                Some(split) if &arg[..split] == "hints" => {
+                    hints = str::FromStr::from_str(&arg[split + 1..]).unwrap();
+                }
+                _ => println!("Ignoring unrecognized argument {}", arg),
+            }
+        }
+
+        if match_games == 0 {
+            panic!("A match must be at least one game");
+        }
+
+        // Five-card cribbage is a two player game played to 61 unless told otherwise
+        if five_card && num_players != 2 {
+            panic!("Five-card cribbage is played by two players");
+        }
+        let mut points_to_win = points_to_win.unwrap_or(if five_card { 61 } else { 121 });
+
+        // The game object ends the game at 121 points, so a game can be no longer
+        if points_to_win == 0 || points_to_win > 121 {
+            panic!("Points to win must be between 1 and 121");
+        }
+
+        // Captain's cribbage is one player against two, where the captain needs about half the
+        // points the team does; 61 against 121 unless told otherwise. Scores are compared against
+        // different targets, so it can not be Lowball
+        let captain_points_to_win_given = captain_points_to_win.is_some();
+        let captain_points_to_win = captain_points_to_win.unwrap_or((points_to_win + 1) / 2);
+        if captain {
+            if num_players != 3 || teams.is_some() {
+                panic!("Captain's cribbage is played by three players without teams");
+            }
+            if lowball {
+                panic!("Captain's cribbage can not be played as Lowball");
+            }
+            if captain_points_to_win == 0 || captain_points_to_win > 121 {
+                panic!("Points for the captain to win must be between 1 and 121");
+            }
+        } else if captain_points_to_win_given {
+            panic!("Points for the captain to win need captain=true");
+        }
+
+        // A practice table seats one player and can not be ranked
+        if practice.is_some() {
+            if num_players != 1 {
+                panic!("A practice table has one player");
+            }
+            if ranked {
+                panic!("A practice table can not be ranked");
+            }
+        }
+
+        // Six-handed cribbage is only played in partnerships, either three teams of two or two
+        // teams of three
+        if practice.is_none() && (num_players < 2 || num_players > 6) {
+            panic!("A table must have between two and six players");
+        }
+        if num_players == 6 && teams.is_none() {
+            panic!("Six players must be split into teams with teams=2 or teams=3");
+        }
+
+        // Every team must have the same number of players and at least two of them
+        if let Some(teams) = teams {
+            if teams < 2 || num_players % teams != 0 || num_players / teams < 2 {
+                panic!(
+                    "{} players can not be split into {} teams",
+                    num_players, teams
+                );
+            }
+        }
+
+        // Seats are reserved for accounts, so the players taking them must log in
+        if !reserved_seats.is_empty() && !require_login && !ranked {
+            panic!("Reserved seats require login=true");
+        }
+
+        // Ratings are only comparable between games played under the same rules by known players,
+        // so a ranked table overrides the scoring options and game length, requires accounts, and
+        // refuses stacked decks. Ratings compare two sides, so only two player tables and tables of
+        // two teams can be ranked
+        let (man_scoring, underpegging, muggins, overpegging) = if ranked {
+            if num_players != 2 && teams != Some(2) {
+                panic!("Only tables of two players or two teams can be ranked");
+            }
+            if deck_path.is_some() {
+                panic!("Stacked decks cannot be used at a ranked table");
+            }
+            println!("Ranked table; using standard rules and requiring login");
+            require_login = true;
+            points_to_win = 121;
+            lowball = false;
+            five_card = false;
+            auction = false;
+            hints = false;
+            (false, false, false, false)
+        } else {
+            (man_scoring, underpegging, muggins, overpegging)
+        };
+
+        // A stacked deck file falls back to shuffling from a seed once its decks run out; when no
+        // seed is given one is taken from the clock so that it can still be recorded
+        let start_time = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let seed = seed.unwrap_or(start_time);
+        let deck_setup = match deck_path {
+            None => super::deck::DeckSetup::Seeded(seed),
+            Some(path) => super::deck::DeckSetup::Stacked {
+                decks: super::deck::read_stacked_decks(&path).unwrap(),
+                seed: seed,
+            },
+        };
+
+        // Every table gets its own log and snapshot; by default named after the port and the time
+        // it was opened
+        TableConfig {
+            log_path: log_path.unwrap_or(format!("game_{}_{}.log", port, start_time)),
+            snapshot_path: snapshot_path
+                .unwrap_or(format!("game_{}_{}.snapshot", port, start_time)),
+            port: port,
+            websocket_port: websocket_port,
+            tls_certificate: tls_certificate,
+            tls_key: tls_key,
+            num_players: num_players,
+            teams: teams,
+            man_scoring: man_scoring,
+            underpegging: underpegging,
+            muggins: muggins,
+            overpegging: overpegging,
+            points_to_win: points_to_win,
+            lowball: lowball,
+            five_card: five_card,
+            auction: auction,
+            practice: practice,
+            captain: captain,
+            hints: hints,
+            captain_points_to_win: captain_points_to_win,
+            deck_setup: deck_setup,
+            require_login: require_login,
+            accounts_path: accounts_path,
+            ranked: ranked,
+            match_games: match_games,
+            skunk_counts_double: skunk_counts_double,
+            reserved_seats: reserved_seats,
+            result_path: result_path,
+            tournament_path: tournament_path,
+            stats_path: stats_path,
+        }
+    }
+}
+
+// The score a loser must reach to avoid being skunked in a game to the given points
+fn skunk_line_for_points(points_to_win: u8) -> u8 {
+    if points_to_win >= 61 {
+        points_to_win - 30
+    } else {
+        points_to_win / 2
+    }
+}
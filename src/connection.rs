@@ -0,0 +1,314 @@
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::net;
+use std::time;
+use tungstenite::protocol::Message;
+
+// How messages are encoded on a connection; chosen by the client when it connects
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Encoding {
+    // Each message is a bincode serialized ClientToGame or GameToClient preceded by its length in
+    // bytes as a four byte little-endian integer; the default
+    Bincode,
+
+    // Each message is a ClientToGame or GameToClient serialized as JSON on its own line
+    Json,
+}
+
+// The number of bytes in the length prefix of a bincode message, and the longest message a client
+// may send; a longer length is taken to mean the client is not speaking the protocol
+const LENGTH_PREFIX: usize = 4;
+const MAX_MESSAGE_LENGTH: usize = 64 * 1024;
+
+// How long a client has to complete the TLS handshake after connecting
+const HANDSHAKE_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
+// The stream to a client, either plaintext for local development or encrypted with TLS when the
+// table is given a certificate and key
+pub enum Stream {
+    Plain(net::TcpStream),
+    Tls(native_tls::TlsStream<net::TcpStream>),
+}
+
+impl Stream {
+    // Wraps a newly accepted socket, performing the TLS handshake when the table uses TLS. The
+    // handshake is given HANDSHAKE_TIMEOUT so that a client which connects and says nothing does
+    // not hold its thread forever
+    pub fn accept(
+        socket: net::TcpStream,
+        tls_acceptor: &Option<native_tls::TlsAcceptor>,
+    ) -> Result<Stream, String> {
+        match tls_acceptor {
+            Some(tls_acceptor) => {
+                if let Err(e) = socket
+                    .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+                    .and_then(|_| socket.set_write_timeout(Some(HANDSHAKE_TIMEOUT)))
+                {
+                    return Err(format!("Could not set the TLS handshake timeout; {}", e));
+                }
+
+                let tls_stream = match tls_acceptor.accept(socket) {
+                    Ok(tls_stream) => tls_stream,
+                    Err(e) => return Err(format!("TLS handshake failed; {}", e)),
+                };
+
+                let socket = tls_stream.get_ref();
+                match socket
+                    .set_read_timeout(None)
+                    .and_then(|_| socket.set_write_timeout(None))
+                {
+                    Ok(()) => Ok(Stream::Tls(tls_stream)),
+                    Err(e) => Err(format!("Could not clear the TLS handshake timeout; {}", e)),
+                }
+            }
+            None => Ok(Stream::Plain(socket)),
+        }
+    }
+
+    // The underlying TCP stream, used for setting timeouts and blocking
+    fn tcp(&self) -> &net::TcpStream {
+        match self {
+            Stream::Plain(stream) => stream,
+            Stream::Tls(tls_stream) => tls_stream.get_ref(),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buffer),
+            Stream::Tls(tls_stream) => tls_stream.read(buffer),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buffer),
+            Stream::Tls(tls_stream) => tls_stream.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(tls_stream) => tls_stream.flush(),
+        }
+    }
+}
+
+// Reads the certificate and private key, both PEM encoded, to create the TLS acceptor for a table
+pub fn tls_acceptor(
+    certificate_path: &str,
+    key_path: &str,
+) -> Result<native_tls::TlsAcceptor, String> {
+    let certificate = match fs::read(certificate_path) {
+        Ok(certificate) => certificate,
+        Err(e) => return Err(format!("Could not read {}; {}", certificate_path, e)),
+    };
+    let key = match fs::read(key_path) {
+        Ok(key) => key,
+        Err(e) => return Err(format!("Could not read {}; {}", key_path, e)),
+    };
+
+    match native_tls::Identity::from_pkcs8(&certificate, &key) {
+        Ok(identity) => match native_tls::TlsAcceptor::new(identity) {
+            Ok(tls_acceptor) => Ok(tls_acceptor),
+            Err(e) => Err(e.to_string()),
+        },
+        Err(e) => Err(format!("Invalid certificate or key; {}", e)),
+    }
+}
+
+// The number of bytes of the next bincode message in the buffer, the length prefix included, once
+// enough has been received to know it
+fn frame_length(buffer: &Vec<u8>) -> Option<usize> {
+    if buffer.len() < LENGTH_PREFIX {
+        return None;
+    }
+    let mut prefix = [0 as u8; LENGTH_PREFIX];
+    prefix.copy_from_slice(&buffer[..LENGTH_PREFIX]);
+    Some(LENGTH_PREFIX + u32::from_le_bytes(prefix) as usize)
+}
+
+// Whether or not the buffer holds a whole message in the given encoding
+fn has_message(buffer: &Vec<u8>, encoding: Encoding) -> bool {
+    match encoding {
+        Encoding::Bincode => match frame_length(buffer) {
+            Some(length) => buffer.len() >= length,
+            None => false,
+        },
+        Encoding::Json => buffer.contains(&b'\n'),
+    }
+}
+
+// What the messages to and from a client are carried over
+enum Transport {
+    // A stream carrying messages directly
+    Tcp(Stream),
+
+    // A WebSocket from a browser client; each WebSocket message carries one ClientToGame or
+    // GameToClient message
+    WebSocket(tungstenite::WebSocket<Stream>),
+}
+
+// A connection to a client that sends GameToClient messages and receives ClientToGame messages in
+// the encoding the client asked for
+pub struct Connection {
+    transport: Transport,
+    encoding: Encoding,
+
+    // Bytes received from the client that have yet to make a complete message; a line for JSON
+    // clients and a length prefix and the message it gives the length of for bincode clients
+    buffer: Vec<u8>,
+}
+
+impl Connection {
+    // Decides the encoding of a new connection from the first byte the client sends. Every client
+    // opens the connection with a Greeting in the encoding it wants; a JSON Greeting is the line
+    // "Greeting" including the quotes so starts with a quote, or a brace for a JSON object, which
+    // the length prefix of a bincode Greeting never does. The Greeting is consumed here as the
+    // client handler sends its own to the game
+    pub fn negotiate(mut stream: Stream) -> Result<Connection, String> {
+        let mut first_byte = [0 as u8; 1];
+        if let Err(e) = stream.read_exact(&mut first_byte) {
+            return Err(format!(
+                "Client closed the connection before greeting; {}",
+                e
+            ));
+        }
+
+        let encoding = if first_byte[0] == b'"' || first_byte[0] == b'{' {
+            Encoding::Json
+        } else {
+            Encoding::Bincode
+        };
+        let mut connection = Connection {
+            transport: Transport::Tcp(stream),
+            encoding: encoding,
+            buffer: vec![first_byte[0]],
+        };
+
+        if connection.receive()? != Some(super::messages::ClientToGame::Greeting) {
+            return Err(format!(
+                "{:?} client did not open with a Greeting",
+                encoding
+            ));
+        }
+
+        println!("Client is using {:?}", connection.encoding);
+
+        Ok(connection)
+    }
+
+    // Performs the WebSocket handshake with a browser client. WebSocket clients send and receive
+    // JSON in text messages, though a ClientToGame in a binary message is read as bincode
+    pub fn websocket(stream: Stream) -> Result<Connection, String> {
+        match tungstenite::accept(stream) {
+            Ok(websocket) => Ok(Connection {
+                transport: Transport::WebSocket(websocket),
+                encoding: Encoding::Json,
+                buffer: Vec::new(),
+            }),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        match &self.transport {
+            Transport::Tcp(stream) => stream.tcp().set_nonblocking(nonblocking).unwrap(),
+            Transport::WebSocket(websocket) => websocket
+                .get_ref()
+                .tcp()
+                .set_nonblocking(nonblocking)
+                .unwrap(),
+        }
+    }
+
+    // Sends the message to the client; returns an error if the connection has failed, after which
+    // nothing more can be sent
+    pub fn send(&mut self, message: &super::messages::GameToClient) -> Result<(), String> {
+        let sent = match &mut self.transport {
+            Transport::Tcp(stream) => {
+                let bytes = match self.encoding {
+                    Encoding::Bincode => {
+                        let message = bincode::serialize(message).unwrap();
+                        let mut frame = (message.len() as u32).to_le_bytes().to_vec();
+                        frame.extend(message);
+                        frame
+                    }
+                    Encoding::Json => {
+                        let mut line = serde_json::to_vec(message).unwrap();
+                        line.push(b'\n');
+                        line
+                    }
+                };
+                stream
+                    .write_all(&bytes)
+                    .and_then(|_| stream.flush())
+                    .map_err(|e| e.to_string())
+            }
+            Transport::WebSocket(websocket) => websocket
+                .send(Message::Text(serde_json::to_string(message).unwrap()))
+                .map_err(|e| e.to_string()),
+        };
+
+        sent.map_err(|e| format!("Could not send to the client; {}", e))
+    }
+
+    // Reads a message from the client; blocks until one arrives unless the connection is
+    // non-blocking, in which case None is returned when there is nothing to read. None is also
+    // returned when what was received is not a valid message. An error is returned when the
+    // client has closed the connection or it has failed, after which nothing more can be read
+    pub fn receive(&mut self) -> Result<Option<super::messages::ClientToGame>, String> {
+        let stream = match &mut self.transport {
+            Transport::Tcp(stream) => stream,
+            Transport::WebSocket(websocket) => {
+                return match websocket.read() {
+                    Ok(Message::Text(text)) => Ok(serde_json::from_str(&text).ok()),
+                    Ok(Message::Binary(bytes)) => Ok(bincode::deserialize(&bytes).ok()),
+                    Ok(Message::Close(_)) => Err("Client closed the connection".to_string()),
+                    Ok(_) => Ok(None),
+                    Err(tungstenite::Error::Io(ref e)) if e.kind() == io::ErrorKind::WouldBlock => {
+                        Ok(None)
+                    }
+                    Err(e) => Err(e.to_string()),
+                };
+            }
+        };
+
+        // Read until there is a whole message in the buffer
+        while !has_message(&self.buffer, self.encoding) {
+            if let Some(length) = frame_length(&self.buffer) {
+                if self.encoding == Encoding::Bincode && length > LENGTH_PREFIX + MAX_MESSAGE_LENGTH
+                {
+                    return Err(format!("Client sent a message of {} bytes", length));
+                }
+            }
+
+            let mut packet_from_client = [0 as u8; 256];
+            match stream.read(&mut packet_from_client) {
+                Ok(0) => return Err("Client closed the connection".to_string()),
+                Ok(length) => self.buffer.extend_from_slice(&packet_from_client[..length]),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        match self.encoding {
+            Encoding::Bincode => {
+                let length = frame_length(&self.buffer).unwrap();
+                let frame: Vec<u8> = self.buffer.drain(..length).collect();
+                Ok(bincode::deserialize(&frame[LENGTH_PREFIX..]).ok())
+            }
+            Encoding::Json => {
+                let end = self.buffer.iter().position(|byte| *byte == b'\n').unwrap();
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                Ok(serde_json::from_slice(&line).ok())
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+extern crate cribbage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// How the decks used by the game object are ordered; given from the command line so that a
+// reported deal can be replayed exactly or so that specific hands can be forced for testing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DeckSetup {
+    // Every deck is shuffled by a generator started from the given seed
+    Seeded(u64),
+
+    // Every deck is taken in order from the given list of decks; when the list runs out, the
+    // remaining decks are shuffled by a generator started from the given seed
+    Stacked { decks: Vec<Vec<String>>, seed: u64 },
+}
+
+// A simple xorshift64* generator; used instead of an external crate so that a seed gives the same
+// decks regardless of dependency versions
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeckShuffler {
+    setup: DeckSetup,
+    state: u64,
+    decks_used: usize,
+}
+
+impl DeckShuffler {
+    pub fn new(setup: DeckSetup) -> DeckShuffler {
+        let seed = match &setup {
+            DeckSetup::Seeded(seed) => *seed,
+            DeckSetup::Stacked { seed, .. } => *seed,
+        };
+
+        // A state of zero would only ever produce zeroes
+        DeckShuffler {
+            setup: setup,
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+            decks_used: 0,
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Returns the next deck to be given to the game object, either the next stacked deck or a
+    // freshly shuffled one
+    pub fn next_deck(&mut self) -> Vec<cribbage::deck::Card> {
+        self.decks_used += 1;
+
+        if let DeckSetup::Stacked { decks, .. } = &self.setup {
+            if self.decks_used <= decks.len() {
+                return decks[self.decks_used - 1]
+                    .iter()
+                    .map(|card| parse_card(card).unwrap())
+                    .collect();
+            }
+        }
+
+        // Fisher-Yates shuffle of a fresh deck
+        let mut deck = full_deck();
+        for i in (1..deck.len()).rev() {
+            let j = (self.next_random() % (i as u64 + 1)) as usize;
+            deck.swap(i, j);
+        }
+        deck
+    }
+
+    // Replaces the deck of the game object with the next deck and returns it so that it can be
+    // logged; must be called before any event that draws from the deck, ie. the initial cut and
+    // the deal
+    pub fn stack_deck(&mut self, game_object: &mut cribbage::Game) -> Vec<cribbage::deck::Card> {
+        let deck = self.next_deck();
+        game_object.deck = deck.clone();
+        deck
+    }
+}
+
+// Every card in the deck ordered by suit then value
+pub fn full_deck() -> Vec<cribbage::deck::Card> {
+    let mut deck = Vec::new();
+    for suit in "CDHS".chars() {
+        for value in &[
+            "A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K",
+        ] {
+            deck.push(parse_card(&format!("{}{}", value, suit)).unwrap());
+        }
+    }
+    deck
+}
+
+// Parses cards written as a value followed by a suit such as "AS", "10H", "TH" or "5c"
+pub fn parse_card(card: &str) -> Result<cribbage::deck::Card, String> {
+    let card = card.trim().to_uppercase();
+    if card.len() < 2 {
+        return Err(format!("Card {} is too short", card));
+    }
+    let (value, suit) = card.split_at(card.len() - 1);
+
+    let value = match value {
+        "A" => cribbage::deck::CardValue::Ace,
+        "2" => cribbage::deck::CardValue::Two,
+        "3" => cribbage::deck::CardValue::Three,
+        "4" => cribbage::deck::CardValue::Four,
+        "5" => cribbage::deck::CardValue::Five,
+        "6" => cribbage::deck::CardValue::Six,
+        "7" => cribbage::deck::CardValue::Seven,
+        "8" => cribbage::deck::CardValue::Eight,
+        "9" => cribbage::deck::CardValue::Nine,
+        "10" | "T" => cribbage::deck::CardValue::Ten,
+        "J" => cribbage::deck::CardValue::Jack,
+        "Q" => cribbage::deck::CardValue::Queen,
+        "K" => cribbage::deck::CardValue::King,
+        _ => return Err(format!("Card {} has an invalid value", card)),
+    };
+
+    let suit = match suit {
+        "C" => cribbage::deck::CardSuit::Clubs,
+        "D" => cribbage::deck::CardSuit::Diamonds,
+        "H" => cribbage::deck::CardSuit::Hearts,
+        "S" => cribbage::deck::CardSuit::Spades,
+        _ => return Err(format!("Card {} has an invalid suit", card)),
+    };
+
+    Ok(cribbage::deck::Card {
+        value: value,
+        suit: suit,
+    })
+}
+
+// Reads a file of stacked decks; every non-empty line is one deck of 52 comma or whitespace
+// separated cards, top of the deck first
+pub fn read_stacked_decks(path: &str) -> Result<Vec<Vec<String>>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return Err(format!("Could not read deck file {}; {}", path, e)),
+    };
+
+    let mut decks = Vec::new();
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let deck: Vec<String> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|card| !card.is_empty())
+            .map(|card| card.to_string())
+            .collect();
+
+        // Check that the deck is complete and has no duplicates
+        if deck.len() != 52 {
+            return Err(format!(
+                "Deck {} has {} cards instead of 52",
+                decks.len() + 1,
+                deck.len()
+            ));
+        }
+        let mut parsed = Vec::new();
+        for card in &deck {
+            let card = parse_card(card)?;
+            if parsed.contains(&card) {
+                return Err(format!("Deck {} has a duplicate card", decks.len() + 1));
+            }
+            parsed.push(card);
+        }
+
+        decks.push(deck);
+    }
+
+    Ok(decks)
+}
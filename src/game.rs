@@ -1,11 +1,14 @@
 extern crate cribbage;
-use std::sync::mpsc;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{mpsc, Arc, Mutex};
 use std::{thread, time};
 
 // TODO Handle all the unwraps and do proper error handling and all
 
-#[derive(PartialEq, Debug)]
-enum GciState {
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum GciState {
     // The state given to a client that has just connected and who has yet to send the greeting
     Connecting,
 
@@ -15,12 +18,18 @@ enum GciState {
     // Any players who have joined the table and who have been asked for a name
     WaitingName,
 
+    // Any players who have joined a table requiring accounts and who have been asked to log in
+    WaitingLogin,
+
     // The player who has been requested confirmation for the cut
     WaitingForInitialCut,
 
     // The dealer who's confirmation deals the hands
     WaitingForDeal,
 
+    // The players who need to bid for the crib in Auction cribbage
+    WaitingForBid,
+
     // The players who need to select one or two cards to discard
     WaitingForDiscards,
 
@@ -36,6 +45,14 @@ enum GciState {
     WaitingForPlay,
     WaitingForPlayScore,
 
+    // The player at a Cribbage Squares practice table who has been dealt a card to place in the
+    // grid, and who has been asked whether to start another grid
+    WaitingForPlacement,
+    WaitingForNewGrid,
+
+    // The player at a counting practice table who has been dealt a hand and starter to count
+    WaitingForHandCount,
+
     // When the client has disconnected and the GCI should be cleaned up
     Disconnected,
 
@@ -45,17 +62,48 @@ enum GciState {
 
 // A structure tying a player index in the game to a transmitter and receiver for a client handling
 // thread
-struct GameClientInterface {
+pub struct GameClientInterface {
     // The player index in the Game players vector that corresponds to this client
-    index: Option<u8>,
+    pub index: Option<u8>,
+
+    // The username of the account the client has logged in to, if any
+    pub account: Option<String>,
 
     // The transmitter and receiver to the thread handling the client
-    transmitter: mpsc::Sender<super::messages::GameToClient>,
-    receiver: mpsc::Receiver<super::messages::ClientToGame>,
+    pub transmitter: mpsc::Sender<super::messages::GameToClient>,
+    pub receiver: mpsc::Receiver<super::messages::ClientToGame>,
 
     // The state of the client, what input is required or that the client is waiting for input from
     // the server
-    state: GciState,
+    pub state: GciState,
+
+    // Messages from the client that arrived while a message sent to it was waiting to be
+    // acknowledged; handled before anything else the client sends
+    pub pending: VecDeque<super::messages::ClientToGame>,
+}
+
+impl GameClientInterface {
+    pub fn new(
+        transmitter: mpsc::Sender<super::messages::GameToClient>,
+        receiver: mpsc::Receiver<super::messages::ClientToGame>,
+    ) -> GameClientInterface {
+        GameClientInterface {
+            index: None,
+            account: None,
+            transmitter: transmitter,
+            receiver: receiver,
+            state: GciState::Connecting,
+            pending: VecDeque::new(),
+        }
+    }
+
+    // Returns the next message from the client without waiting for one
+    pub fn try_receive(&mut self) -> Result<super::messages::ClientToGame, mpsc::TryRecvError> {
+        match self.pending.pop_front() {
+            Some(message) => Ok(message),
+            None => self.receiver.try_recv(),
+        }
+    }
 }
 
 // A structure used to forward ClientToGame messages from the receiver in the GameClientInterface
@@ -74,7 +122,8 @@ struct ClientMessage {
 // order that they joined the game such as during the initial cut. If the active index starts at
 // (index_dealer + 1) % num_players, has no stop point, and requires PlayEvents then it will ask
 // for PlayEvents from each player in a circle on loop until the end of the play phase.
-struct OrderedInputTracker {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OrderedInputTracker {
     index_active: u8,
     index_last: Option<u8>,
     index_stop: Option<u8>,
@@ -82,12 +131,16 @@ struct OrderedInputTracker {
 
 // An enum used to keep track of the player inputs that need to be held for the next process_event
 // call to the GameObject
-enum InputStore {
+#[derive(Clone, Serialize, Deserialize)]
+pub enum InputStore {
     Names(Vec<String>),
+    // Pairs of player indices and the points they bid for the crib
+    Bids(Vec<(u8, u8)>),
     Discards(Vec<DiscardSelection>),
 }
 
-enum DiscardSelection {
+#[derive(Clone, Serialize, Deserialize)]
+pub enum DiscardSelection {
     OneDiscard {
         player_index: u8,
         card_index: u8,
@@ -99,12 +152,129 @@ enum DiscardSelection {
     },
 }
 
-// Sends a message to a given client interface and assures that the message has been received
-// TODO Get it to return an error if the message is not TransmissionReceived instead of using an
-// assert
-fn send_message(message: super::messages::GameToClient, gci: &mut GameClientInterface) {
-    gci.transmitter.send(message).unwrap();
-    assert!(gci.receiver.recv() == Ok(super::messages::ClientToGame::TransmissionReceived));
+// Sends a message to a given client interface and assures that the message has been received. A
+// client may answer an earlier request before acknowledging the message, so anything else it
+// sends in the meantime is kept for the game loop. A client whose handler thread has ended,
+// because the client disconnected, is marked Disconnected and is sent nothing more
+pub fn send_message(message: super::messages::GameToClient, gci: &mut GameClientInterface) {
+    if gci.state == GciState::Disconnected {
+        return;
+    }
+
+    if gci.transmitter.send(message).is_err() {
+        gci.state = GciState::Disconnected;
+        return;
+    }
+    loop {
+        match gci.receiver.recv() {
+            Ok(super::messages::ClientToGame::TransmissionReceived) => break,
+            Ok(message) => gci.pending.push_back(message),
+            Err(_) => {
+                gci.state = GciState::Disconnected;
+                break;
+            }
+        }
+    }
+}
+
+// Sends a message to every client at the table, players and spectators alike, and records it in
+// the game log
+fn broadcast(
+    message: super::messages::GameToClient,
+    gcis: &mut Vec<GameClientInterface>,
+    game_log: &mut super::log::GameLog,
+) {
+    log_entry(game_log, super::log::LogEntry::Broadcast(message.clone()));
+    for gci in gcis {
+        send_message(message.clone(), gci);
+    }
+}
+
+// Records the entry in the game log. A failed write is only reported here; the log refuses every
+// later entry and the game loop ends the game once the step it is on is done
+fn log_entry(game_log: &mut super::log::GameLog, entry: super::log::LogEntry) {
+    if let Err(e) = game_log.append(&entry) {
+        println!("{}", e);
+    }
+}
+
+// Records the event in the game log then gives it to the game object; returns the reason the game
+// object rejected the event as an error
+fn process_event(
+    game_object: &mut cribbage::Game,
+    event: super::log::LoggedEvent,
+    game_log: &mut super::log::GameLog,
+) -> Result<(), String> {
+    log_entry(game_log, super::log::LogEntry::Event(event.clone()));
+    match game_object.process_event(event.to_game_event()) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Event {:?} failed; {}", event, e)),
+    }
+}
+
+// Replaces the deck of the game object with the next deck from the shuffler and records it in the
+// game log so that the game can be replayed
+fn stack_deck(
+    game_object: &mut cribbage::Game,
+    deck_shuffler: &mut super::deck::DeckShuffler,
+    game_log: &mut super::log::GameLog,
+) {
+    let deck = deck_shuffler.stack_deck(game_object);
+    log_entry(game_log, super::log::LogEntry::Deck(deck));
+}
+
+// The longest name a player may go by, in characters
+const MAX_NAME_LENGTH: usize = 24;
+
+// Checks that a name is one a player or account may go by; names are shown to other players so they
+// must not be empty, overly long, or contain control characters. Returns the reason a name is
+// rejected as an error
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name can not be empty".to_string());
+    }
+
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "Name can not be longer than {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+
+    if name.chars().any(|c| c.is_control()) {
+        return Err("Name can not contain control characters".to_string());
+    }
+
+    Ok(())
+}
+
+// Checks that a name is one a player may go by at the table; it must pass check_name and not match
+// the name of another player ignoring case. Returns the reason a name is rejected as an error
+fn validate_name(name: &str, player_index: u8, names: &Vec<String>) -> Result<(), String> {
+    check_name(name)?;
+
+    for (index, other_name) in names.iter().enumerate() {
+        if index != player_index as usize && other_name.to_lowercase() == name.to_lowercase() {
+            return Err("Name is already in use".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// Checks that a name given at a table without logins is not the username of an account, so that
+// no one can sit under another player's account name and have their games mistaken for the
+// account's. If the account store can not be read the name is allowed, as the table does not
+// depend on accounts; returns the name or the reason it is rejected
+pub fn check_unregistered(name: &str, accounts_path: &str) -> Result<String, String> {
+    match super::accounts::is_registered(accounts_path, name) {
+        Ok(true) => Err("That name belongs to a registered account".to_string()),
+        Ok(false) => Ok(name.to_string()),
+        Err(e) => {
+            println!("Could not check for a registered name; {}", e);
+            Ok(name.to_string())
+        }
+    }
 }
 
 // Simply returns whether or not all players in a vector of GameClientInterfaces are waiting for a
@@ -142,18 +312,648 @@ fn send_hands(game_object: &cribbage::Game, clients: &mut Vec<GameClientInterfac
     }
 }
 
+// The number of cards the player in the given seat should be dealt. Two players are dealt six
+// cards, or five in five-card cribbage; three, four, and six players five cards; and with five
+// players the dealer is dealt four cards while the others are dealt five so that the four
+// non-dealers alone discard to the crib
+fn dealt_hand_size(config: &super::config::TableConfig, seat: u8, dealer: u8) -> usize {
+    match config.num_players {
+        2 if config.five_card => 5,
+        2 => 6,
+        5 if seat == dealer => 4,
+        _ => 5,
+    }
+}
+
+// The number of cards each player keeps after discarding; three in five-card cribbage and four
+// otherwise
+fn kept_hand_size(config: &super::config::TableConfig) -> usize {
+    if config.five_card {
+        3
+    } else {
+        4
+    }
+}
+
+// The number of cards the player in the given seat must discard to the crib; however many more
+// than they keep they are dealt. With two players that is two cards, with three, four, or six
+// players one card, and with five players the dealer is dealt four cards and discards none. Hands
+// are checked against dealt_hand_size when they are dealt
+fn discards_required(config: &super::config::TableConfig, seat: u8, dealer: u8) -> usize {
+    dealt_hand_size(config, seat, dealer).saturating_sub(kept_hand_size(config))
+}
+
+// The number of cards dealt straight to the crib; one with three players and none otherwise
+fn kitty_size(config: &super::config::TableConfig) -> usize {
+    if config.num_players == 3 {
+        1
+    } else {
+        0
+    }
+}
+
+// The number of cards in the crib once every player has discarded; the kitty and the discards of
+// every player. That is four cards except with six players, who make a crib of six
+fn crib_size(config: &super::config::TableConfig, dealer: u8) -> usize {
+    kitty_size(config)
+        + (0..config.num_players)
+            .map(|seat| discards_required(config, seat, dealer))
+            .sum::<usize>()
+}
+
+// Checks the indices a player gave for their discards against their hand; returns the reason the
+// discards are rejected if an index is past the end of the hand or the same card is given twice
+fn check_discards(hand: &Vec<cribbage::deck::Card>, indices: &[u8]) -> Result<(), String> {
+    for (position, index) in indices.iter().enumerate() {
+        if *index as usize >= hand.len() {
+            return Err(format!("Your hand has no card at index {}", index));
+        }
+        if indices[..position].contains(index) {
+            return Err("Discard two different cards".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// Whether the same card is in the given cards more than once
+fn has_duplicates(cards: &Vec<cribbage::deck::Card>) -> bool {
+    cards
+        .iter()
+        .enumerate()
+        .any(|(position, card)| cards[..position].contains(card))
+}
+
+// Asks every player who must discard for their discards; a player dealt only the cards they keep,
+// such as the dealer when there are five players, does not discard
+fn request_discards(
+    game_object: &cribbage::Game,
+    config: &super::config::TableConfig,
+    gcis: &mut Vec<GameClientInterface>,
+) {
+    for gci in gcis {
+        let index = match gci.index {
+            Some(index) => index,
+            None => continue,
+        };
+
+        match discard_request(discards_required(config, index, game_object.index_dealer)) {
+            Some(request) => {
+                gci.state = GciState::WaitingForDiscards;
+                send_message(request, gci);
+            }
+            None => gci.state = GciState::WaitingForServer,
+        }
+    }
+}
+
+// The dealer in effect for the hand; the winner of the auction for the crib in Auction cribbage,
+// who plays and counts last, owns the crib, and takes his heels, and the player who dealt otherwise
+fn effective_dealer(game_object: &cribbage::Game, crib_owner: Option<u8>) -> u8 {
+    crib_owner.unwrap_or(game_object.index_dealer)
+}
+
+// The winner of the auction for the crib and their bid; the highest bid wins and a tie goes to
+// whichever of the tied players comes first in turn order from the dealer, the dealer included
+fn auction_winner(bids: &Vec<(u8, u8)>, dealer: u8, num_players: u8) -> (u8, u8) {
+    let highest = bids.iter().map(|(_, bid)| *bid).max().unwrap_or(0);
+    let winner = (0..num_players)
+        .map(|offset| (dealer + offset) % num_players)
+        .find(|seat| {
+            bids.iter()
+                .any(|(player, bid)| player == seat && *bid == highest)
+        })
+        .unwrap_or(dealer);
+    (winner, highest)
+}
+
+// The seat given three points for last at the start of a game of five-card cribbage, to make up
+// for the dealer having the first crib, and the points; None in every other game
+fn points_for_last(config: &super::config::TableConfig, dealer: u8) -> Option<(u8, u8)> {
+    if config.five_card {
+        Some(((dealer + 1) % config.num_players, 3))
+    } else {
+        None
+    }
+}
+
+// Gives the non-dealer their points for last, if any, and announces the scores
+fn give_last(
+    game_object: &mut cribbage::Game,
+    config: &super::config::TableConfig,
+    gcis: &mut Vec<GameClientInterface>,
+    game_log: &mut super::log::GameLog,
+) {
+    if let Some((non_dealer, points)) = points_for_last(config, game_object.index_dealer) {
+        peg(game_object, non_dealer, points, game_log);
+        broadcast(score_update(game_object, config), gcis, game_log);
+    }
+}
+
+// Pegs points for a player outside of the scoring of the game object, logging them so that the
+// game can be rebuilt
+fn peg(
+    game_object: &mut cribbage::Game,
+    player: u8,
+    points: u8,
+    game_log: &mut super::log::GameLog,
+) {
+    if points > 0 {
+        log_entry(
+            game_log,
+            super::log::LogEntry::Points {
+                player: player,
+                points: points,
+            },
+        );
+        super::log::give_points(game_object, player, points);
+    }
+}
+
+// The total points of the scores the game library finds in a hand or crib
+fn score_points(scores: &Vec<cribbage::score::ScoreEvent>) -> u8 {
+    scores.iter().fold(0 as u8, |total, score| {
+        total.saturating_add(score.point_value())
+    })
+}
+
+// The account the player in the given seat is logged in to, if any
+fn seat_account(gcis: &Vec<GameClientInterface>, seat: u8) -> Option<String> {
+    gcis.iter()
+        .find(|gci| gci.index == Some(seat))
+        .and_then(|gci| gci.account.clone())
+}
+
+// Settles the points a player claimed for a count under the scoring options of the table, given
+// the points of the claim that are right, that are wrong, and that were missed; returns the points
+// the player pegs and the points they missed, or the message rejecting the claim when the table
+// does not let players claim too many or too few points. Points claimed wrongly stand when the
+// table allows overpegging
+fn settle_claim(
+    config: &super::config::TableConfig,
+    right: u8,
+    wrong: u8,
+    missed: u8,
+) -> Result<(u8, u8), super::messages::GameToClient> {
+    if wrong > 0 && !config.overpegging {
+        Err(super::messages::GameToClient::InvalidPlayScoring)
+    } else if missed > 0 && !config.underpegging {
+        Err(super::messages::GameToClient::IncompletePlayScoring)
+    } else {
+        Ok((right.saturating_add(wrong), missed))
+    }
+}
+
+// The seat that takes the points missed by the player in the given seat under muggins; the first
+// seat after them on another side
+fn muggins_seat(config: &super::config::TableConfig, seat: u8) -> u8 {
+    (1..config.num_players)
+        .map(|offset| (seat + offset) % config.num_players)
+        .find(|other| config.side_of(*other) != config.side_of(seat))
+        .unwrap_or(seat)
+}
+
+// Pegs the points a player settled on for a count, giving the points they missed to the seat that
+// takes them when the table plays muggins
+fn peg_claim(
+    game_object: &mut cribbage::Game,
+    config: &super::config::TableConfig,
+    gcis: &mut Vec<GameClientInterface>,
+    game_log: &mut super::log::GameLog,
+    seat: u8,
+    pegged: u8,
+    missed: u8,
+) {
+    peg(game_object, seat, pegged, game_log);
+    if missed > 0 && config.muggins {
+        let player = muggins_seat(config, seat);
+        peg(game_object, player, missed, game_log);
+        broadcast(
+            super::messages::GameToClient::Muggins {
+                player: player,
+                points: missed,
+            },
+            gcis,
+            game_log,
+        );
+    }
+}
+
+// Announces the card a player played in the play with the points they settled on for it, and
+// pegs those points
+fn peg_card(
+    game_object: &mut cribbage::Game,
+    config: &super::config::TableConfig,
+    gcis: &mut Vec<GameClientInterface>,
+    game_log: &mut super::log::GameLog,
+    play: &mut super::play::PlayState,
+    seat: u8,
+    card: cribbage::deck::Card,
+    pegged: u8,
+    missed: u8,
+) {
+    play.peg(seat, pegged);
+    broadcast(
+        super::messages::GameToClient::CardPlayed {
+            player: seat,
+            card: card,
+            points: pegged,
+        },
+        gcis,
+        game_log,
+    );
+    peg_claim(game_object, config, gcis, game_log, seat, pegged, missed);
+    broadcast(score_update(game_object, config), gcis, game_log);
+}
+
+// The cards the player in the given seat counts with the starter in the show; their hand, or the
+// crib they own
+fn show_cards(game_object: &cribbage::Game, seat: u8, is_crib: bool) -> Vec<cribbage::deck::Card> {
+    if is_crib {
+        game_object.crib.clone()
+    } else {
+        game_object.players[seat as usize].hand.clone()
+    }
+}
+
+// The scores the game library finds in the given count of the show
+fn show_scores(
+    game_object: &cribbage::Game,
+    seat: u8,
+    is_crib: bool,
+) -> Vec<cribbage::score::ScoreEvent> {
+    cribbage::score::score_hand(
+        &show_cards(game_object, seat, is_crib),
+        &game_object.starter_card,
+        is_crib,
+    )
+}
+
+// Counts the hand or crib at the front of the show; the count is announced with the scores the
+// game library finds in it and the player pegs the points they settled on, which are recorded in
+// the statistics of their account for a hand. Returns true if a side reached the points to win
+fn count_show(
+    game_object: &mut cribbage::Game,
+    config: &super::config::TableConfig,
+    gcis: &mut Vec<GameClientInterface>,
+    game_log: &mut super::log::GameLog,
+    play: &mut super::play::PlayState,
+    stats_store: &Mutex<super::stats::StatsStore>,
+    pegged: u8,
+    missed: u8,
+) -> bool {
+    let (seat, is_crib) = play.shows.remove(0);
+    let cards = show_cards(game_object, seat, is_crib);
+    let scores = show_scores(game_object, seat, is_crib);
+    let count = if is_crib {
+        super::messages::GameToClient::ShowCrib {
+            player: seat,
+            crib: cards,
+            scores: scores,
+        }
+    } else {
+        super::messages::GameToClient::ShowHand {
+            player: seat,
+            hand: cards,
+            scores: scores,
+        }
+    };
+    broadcast(count, gcis, game_log);
+    peg_claim(game_object, config, gcis, game_log, seat, pegged, missed);
+    broadcast(score_update(game_object, config), gcis, game_log);
+
+    // The statistics are saved with the result of the game
+    if !is_crib {
+        if let Some(account) = seat_account(gcis, seat) {
+            stats_store.lock().unwrap().record_hand(&account, pegged);
+        }
+    }
+
+    winning_side(&side_scores(game_object, config), config, play.dealer).is_some()
+}
+
+// Counts the hands left in the show in order; with manual scoring the player of the next count is
+// instead asked for the scores they find in it. Returns true once every hand has been counted or a
+// side has reached the points to win, in which case the hands after it are not counted
+fn continue_show(
+    game_object: &mut cribbage::Game,
+    config: &super::config::TableConfig,
+    gcis: &mut Vec<GameClientInterface>,
+    game_log: &mut super::log::GameLog,
+    play: &mut super::play::PlayState,
+    stats_store: &Mutex<super::stats::StatsStore>,
+) -> bool {
+    while let Some(&(seat, is_crib)) = play.shows.first() {
+        if config.man_scoring {
+            play.claim = Some(super::play::Claim::Show {
+                seat: seat,
+                is_crib: is_crib,
+            });
+            gcis[seat as usize].state = GciState::WaitingForPlayScore;
+            send_message(
+                show_request(game_object, seat, is_crib),
+                &mut gcis[seat as usize],
+            );
+            return false;
+        }
+
+        let points = score_points(&show_scores(game_object, seat, is_crib));
+        if count_show(
+            game_object,
+            config,
+            gcis,
+            game_log,
+            play,
+            stats_store,
+            points,
+            0,
+        ) {
+            return true;
+        }
+    }
+
+    true
+}
+
+// Ends the play; the points each player pegged in it are recorded in the statistics of their
+// account and the show starts from the seat after the dealer around to the dealer and then the
+// crib for its owner. Returns true once the show is over as for continue_show
+fn show_hands(
+    game_object: &mut cribbage::Game,
+    config: &super::config::TableConfig,
+    gcis: &mut Vec<GameClientInterface>,
+    game_log: &mut super::log::GameLog,
+    play: &mut super::play::PlayState,
+    stats_store: &Mutex<super::stats::StatsStore>,
+) -> bool {
+    {
+        let mut stats_store = stats_store.lock().unwrap();
+        for seat in 0..config.num_players {
+            if let Some(account) = seat_account(gcis, seat) {
+                stats_store.record_pegging(&account, play.pegged[seat as usize]);
+            }
+        }
+    }
+
+    play.start_show();
+    continue_show(game_object, config, gcis, game_log, play, stats_store)
+}
+
+// The message asking the player in the given seat for the scores they find in the given count of
+// the show
+fn show_request(
+    game_object: &cribbage::Game,
+    seat: u8,
+    is_crib: bool,
+) -> super::messages::GameToClient {
+    super::messages::GameToClient::WaitShowScore {
+        cards: show_cards(game_object, seat, is_crib),
+        is_crib: is_crib,
+    }
+}
+
+// The message asking a player to discard the given number of cards, or None if they discard none
+fn discard_request(count: usize) -> Option<super::messages::GameToClient> {
+    match count {
+        0 => None,
+        1 => Some(super::messages::GameToClient::WaitDiscardOne),
+        _ => Some(super::messages::GameToClient::WaitDiscardTwo),
+    }
+}
+
+// Returns the score of each player in order by index
+fn player_scores(game_object: &cribbage::Game) -> Vec<u8> {
+    game_object
+        .players
+        .iter()
+        .map(|player| player.score)
+        .collect()
+}
+
+// Returns the score of each side in order by index; partners pool the points each of them scores
+fn side_scores(game_object: &cribbage::Game, config: &super::config::TableConfig) -> Vec<u8> {
+    let mut scores = vec![0 as u8; config.num_sides() as usize];
+    for (index, score) in player_scores(game_object).into_iter().enumerate() {
+        let side = config.side_of(index as u8) as usize;
+        scores[side] = scores[side].saturating_add(score);
+    }
+    scores
+}
+
+// The ScoreUpdate message announcing the score of each side
+fn score_update(
+    game_object: &cribbage::Game,
+    config: &super::config::TableConfig,
+) -> super::messages::GameToClient {
+    super::messages::GameToClient::ScoreUpdate(
+        side_scores(game_object, config)
+            .into_iter()
+            .enumerate()
+            .map(|(index, score)| (index as u8, score))
+            .collect(),
+    )
+}
+
+// The player indices on each team in order by team index
+fn team_roster(config: &super::config::TableConfig) -> Vec<Vec<u8>> {
+    (0..config.num_sides())
+        .map(|team| {
+            (0..config.num_players)
+                .filter(|seat| config.side_of(*seat) == team)
+                .collect()
+        })
+        .collect()
+}
+
+// The TableRules message announcing the rules of the game
+fn table_rules(config: &super::config::TableConfig) -> super::messages::GameToClient {
+    super::messages::GameToClient::TableRules {
+        points_to_win: config.points_to_win,
+        skunk_line: config.skunk_line(),
+        captain: config.captain_seat(),
+        captain_points_to_win: if config.captain {
+            Some(config.captain_points_to_win)
+        } else {
+            None
+        },
+        lowball: config.lowball,
+        five_card: config.five_card,
+        auction: config.auction,
+        hints: config.hints,
+    }
+}
+
+// The index of the side that won the game once a side has reached the points it needs to win. In
+// Lowball that side loses instead and the side with the fewest points wins. Sides tied on the
+// fewest points are taken in the order they show their hands, from the seat after the dealer, and
+// the first of them wins
+fn winning_side(scores: &Vec<u8>, config: &super::config::TableConfig, dealer: u8) -> Option<u8> {
+    let has_reached_target =
+        |side: usize, score: &u8| *score >= config.points_to_win_of(side as u8);
+    if !scores
+        .iter()
+        .enumerate()
+        .any(|(side, score)| has_reached_target(side, score))
+    {
+        return None;
+    }
+
+    let winner = if config.lowball {
+        let fewest = scores.iter().min().cloned().unwrap_or(0);
+        super::play::show_order(dealer, config.num_players)
+            .into_iter()
+            .map(|seat| config.side_of(seat) as usize)
+            .find(|side| scores[*side] == fewest)
+    } else {
+        scores
+            .iter()
+            .enumerate()
+            .position(|(side, score)| has_reached_target(side, score))
+    };
+    winner.map(|winner| winner as u8)
+}
+
+// Whether or not the winning side skunked any other side; there are no skunks in Lowball
+fn is_skunk(scores: &Vec<u8>, winner: u8, config: &super::config::TableConfig) -> bool {
+    let skunk_lines = skunk_lines(config);
+    scores
+        .iter()
+        .enumerate()
+        .any(|(index, score)| index != winner as usize && *score < skunk_lines[index])
+}
+
+// The skunk line of each side in order by index; every side has the same skunk line except the
+// captain, and nobody is skunked in Lowball
+fn skunk_lines(config: &super::config::TableConfig) -> Vec<u8> {
+    (0..config.num_sides())
+        .map(|side| {
+            if config.lowball {
+                0
+            } else {
+                config.skunk_line_of(side)
+            }
+        })
+        .collect()
+}
+
+// The result of each player in a finished game to be recorded in the statistics of their account;
+// partners share the score and result of their team
+fn seat_results(
+    game_object: &cribbage::Game,
+    config: &super::config::TableConfig,
+    winner: u8,
+    gcis: &Vec<GameClientInterface>,
+) -> Vec<super::stats::SeatResult> {
+    let scores = side_scores(game_object, config);
+
+    let mut results = Vec::new();
+    for index in 0..config.num_players {
+        let mut account = None;
+        for gci in gcis {
+            if gci.index == Some(index) {
+                account = gci.account.clone();
+            }
+        }
+
+        let side = config.side_of(index);
+        results.push(super::stats::SeatResult {
+            account: account,
+            side: side,
+            score: scores[side as usize],
+            won: side == winner,
+        });
+    }
+    results
+}
+
+// Pairs each player index with the name of that player
+fn seat_roster(names: &Vec<String>) -> Vec<(u8, String)> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (index as u8, name.clone()))
+        .collect()
+}
+
+// Saves everything needed to restore the table to the snapshot path given in the config
+fn save_snapshot(
+    config: &super::config::TableConfig,
+    game_object: &cribbage::Game,
+    game_log: &super::log::GameLog,
+    gcis: &Vec<GameClientInterface>,
+    input_store: &InputStore,
+    input_tracker: &Option<OrderedInputTracker>,
+    deck_shuffler: &super::deck::DeckShuffler,
+    match_state: &super::match_play::MatchState,
+    seat_tokens: &Vec<String>,
+    play_state: &Option<super::play::PlayState>,
+    crib_owner: Option<u8>,
+) {
+    // The state of each player and the account they are logged in to in order by index
+    let mut seat_states = Vec::new();
+    let mut seat_accounts = Vec::new();
+    for index in 0..config.num_players {
+        for gci in gcis {
+            if gci.index == Some(index) {
+                seat_states.push(gci.state.clone());
+                seat_accounts.push(gci.account.clone());
+            }
+        }
+    }
+
+    // A snapshot that fails to save leaves the last one in place, so the table carries on
+    let snapshot = super::snapshot::TableSnapshot {
+        config: config.clone(),
+        log_entries: game_log.entries,
+        seat_names: game_object
+            .players
+            .iter()
+            .map(|player| player.username.clone())
+            .collect(),
+        seat_states: seat_states,
+        seat_accounts: seat_accounts,
+        seat_tokens: seat_tokens.clone(),
+        input_store: input_store.clone(),
+        input_tracker: input_tracker.clone(),
+        deck_shuffler: deck_shuffler.clone(),
+        match_state: match_state.clone(),
+        play: play_state.clone(),
+        crib_owner: crib_owner,
+        scores: player_scores(game_object),
+    };
+    if let Err(e) = snapshot.save(&config.snapshot_path) {
+        println!("{}", e);
+    }
+}
+
+// A secret given to a player when they sit so that only they can take their seat back when the
+// table is restored after the server died
+fn reconnect_token() -> String {
+    let mut bytes = [0 as u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
 // Handles the game object
 pub fn handle_game(
     mut game_object: cribbage::Game,
-    num_players: u8,
-    man_scoring: bool,
-    underpegging: bool,
-    muggins: bool,
-    overpegging: bool,
+    // The settings of the table
+    config: super::config::TableConfig,
+    // The state to restore when the table is resumed after the server died; the game object must
+    // already be rebuilt from the game log
+    snapshot: Option<super::snapshot::TableSnapshot>,
+    // The statistics of every account; the result of the game is recorded when it ends
+    stats_store: Arc<Mutex<super::stats::StatsStore>>,
     // Facilitates communication between the main thread and the game thread
     main_receiver: mpsc::Receiver<super::messages::MainToGame>,
     main_transmitter: mpsc::Sender<super::messages::GameToMain>,
 ) {
+    let num_players = config.num_players;
+    let man_scoring = config.man_scoring;
+    let underpegging = config.underpegging;
+    let muggins = config.muggins;
+    let overpegging = config.overpegging;
+
     // A vector containing the game player index that matches the client thread that the
     // transmitter and receiver comunicate with
     let mut client_interfaces: Vec<GameClientInterface> = Vec::new();
@@ -179,6 +979,81 @@ pub fn handle_game(
         }
     }
 
+    // The log of the table; the table can not be played without it
+    let mut game_log = match super::log::GameLog::open(&config.log_path) {
+        Ok(game_log) => game_log,
+        Err(e) => {
+            println!("{}", e);
+            main_transmitter
+                .send(super::messages::GameToMain::EndServer)
+                .unwrap();
+            return;
+        }
+    };
+
+    // Gives the game object its decks
+    let mut deck_shuffler: super::deck::DeckShuffler;
+
+    // The game wins of each seat when the table plays a match of several games
+    let mut match_state = super::match_play::MatchState::new(&config);
+
+    // The play of the hand, from the cut of the starter until the show
+    let mut play_state: Option<super::play::PlayState> = None;
+
+    // The seat that won the crib of the hand in Auction cribbage, who deals in effect for the hand
+    let mut crib_owner: Option<u8> = None;
+
+    // The reconnect token given to the player in each seat when they sat
+    let mut seat_tokens: Vec<String> = vec![String::new(); num_players as usize];
+
+    // When the table is restored from a snapshot, the name, state, and account of the player in
+    // each seat and whether or not that player has reconnected; empty once every player has
+    // reconnected
+    let mut seat_names: Vec<String> = Vec::new();
+    let mut seat_states: Vec<GciState> = Vec::new();
+    let mut seat_accounts: Vec<Option<String>> = Vec::new();
+    let mut seats_reconnected: Vec<bool> = Vec::new();
+
+    match snapshot {
+        Some(snapshot) => {
+            // Discards any entries written between the snapshot and the server dying
+            log_entry(
+                &mut game_log,
+                super::log::LogEntry::Resumed {
+                    entries: snapshot.log_entries,
+                },
+            );
+
+            if player_scores(&game_object) != snapshot.scores {
+                println!("Rebuilt scores do not match the scores in the snapshot");
+            }
+
+            input_store = snapshot.input_store;
+            input_tracker = snapshot.input_tracker;
+            deck_shuffler = snapshot.deck_shuffler;
+            match_state = snapshot.match_state;
+            play_state = snapshot.play;
+            crib_owner = snapshot.crib_owner;
+            num_connected_players = num_players;
+            seats_reconnected = snapshot.seat_names.iter().map(|_| false).collect();
+            seat_names = snapshot.seat_names;
+            seat_states = snapshot.seat_states;
+            seat_accounts = snapshot.seat_accounts;
+            seat_tokens = snapshot.seat_tokens;
+        }
+        None => {
+            // The log starts with the deck setup so that the decks can be checked
+            log_entry(
+                &mut game_log,
+                super::log::LogEntry::DeckSetup(config.deck_setup.clone()),
+            );
+            deck_shuffler = super::deck::DeckShuffler::new(config.deck_setup.clone());
+        }
+    }
+
+    // The number of log entries when the last snapshot was taken
+    let mut snapshot_log_entries = game_log.entries;
+
     // A variable holding the output of the game loop
     let mut output: Result<&str, &str> = Ok("Game thread running");
 
@@ -191,12 +1066,7 @@ pub fn handle_game(
                 receiver,
             }) => {
                 println!("New client_interface");
-                client_interfaces.push(GameClientInterface {
-                    index: None,
-                    state: GciState::Connecting,
-                    transmitter: transmitter,
-                    receiver: receiver,
-                });
+                client_interfaces.push(GameClientInterface::new(transmitter, receiver));
             }
             _ => {}
         };
@@ -204,20 +1074,48 @@ pub fn handle_game(
         // For every client (players and spectators)
         for client_interface in &mut client_interfaces {
             // Handle client messages
-            match client_interface.receiver.try_recv() {
+            match client_interface.try_receive() {
                 // If the client sends a Greeting messages, respond with WaitName or
                 // DeniedTableFull depending on the number of player spots left in the game and the
                 // game state
                 Ok(super::messages::ClientToGame::Greeting) => {
                     println!("Received Greeting");
                     if client_interface.state == GciState::Connecting {
-                        if game_object.state == cribbage::GameState::GameStart
+                        // If the table has been restored, ask for the name or login of the player
+                        // to find which seat they are reconnecting to
+                        if seats_reconnected.contains(&false) {
+                            println!("Reconnecting player");
+                            if config.require_login {
+                                client_interface.state = GciState::WaitingLogin;
+                                send_message(
+                                    super::messages::GameToClient::WaitLogin,
+                                    client_interface,
+                                );
+                            } else {
+                                client_interface.state = GciState::WaitingName;
+                                send_message(
+                                    super::messages::GameToClient::WaitName,
+                                    client_interface,
+                                );
+                            }
+                        } else if game_object.state == cribbage::GameState::GameStart
                             && num_connected_players < num_players
                         {
                             println!("New player");
                             client_interface.index = Some(num_connected_players);
-                            client_interface.state = GciState::WaitingName;
-                            send_message(super::messages::GameToClient::WaitName, client_interface);
+                            if config.require_login {
+                                client_interface.state = GciState::WaitingLogin;
+                                send_message(
+                                    super::messages::GameToClient::WaitLogin,
+                                    client_interface,
+                                );
+                            } else {
+                                client_interface.state = GciState::WaitingName;
+                                send_message(
+                                    super::messages::GameToClient::WaitName,
+                                    client_interface,
+                                );
+                            }
                             num_connected_players += 1;
                         } else {
                             println!("New watcher");
@@ -230,6 +1128,73 @@ pub fn handle_game(
                     }
                 }
 
+                // When a reconnecting player gives their name with the reconnect token they were
+                // given when they sat, or logs in to the account they sat under, return them to
+                // their seat if it is still empty or ask for their token or login again if not. A
+                // name alone is not enough as anyone could type it
+                Ok(message)
+                    if client_interface.index.is_none()
+                        && (client_interface.state == GciState::WaitingName
+                            || client_interface.state == GciState::WaitingLogin) =>
+                {
+                    let seat = match message {
+                        super::messages::ClientToGame::Reconnect { name, token } => seat_names
+                            .iter()
+                            .zip(&seat_tokens)
+                            .position(|(seat_name, seat_token)| {
+                                *seat_name == name && *seat_token == token
+                            })
+                            .ok_or("No seat has that name and reconnect token".to_string()),
+                        super::messages::ClientToGame::LoggedIn(username) => seat_accounts
+                            .iter()
+                            .position(|account| account.as_ref() == Some(&username))
+                            .ok_or("No seat is held by that account".to_string()),
+                        _ => Err("Input is not a reconnect token or login".to_string()),
+                    };
+
+                    let seat = match seat {
+                        Ok(seat) if seats_reconnected[seat] => {
+                            Err("That seat has already been taken back".to_string())
+                        }
+                        seat => seat,
+                    };
+
+                    match seat {
+                        Ok(seat) => {
+                            println!("Player {} reconnected", seat_names[seat]);
+                            seats_reconnected[seat] = true;
+                            client_interface.index = Some(seat as u8);
+                            client_interface.account = seat_accounts[seat].clone();
+                            client_interface.state = GciState::WaitingForServer;
+                            send_message(
+                                super::messages::GameToClient::PlayerJoinNotification {
+                                    name: seat_names[seat].clone(),
+                                    number: seat as u8 + 1,
+                                    of: num_players,
+                                },
+                                client_interface,
+                            );
+                        }
+                        Err(reason) if client_interface.state == GciState::WaitingLogin => {
+                            send_message(
+                                super::messages::GameToClient::LoginRejected(reason),
+                                client_interface,
+                            );
+                            send_message(
+                                super::messages::GameToClient::WaitLogin,
+                                client_interface,
+                            );
+                        }
+                        Err(reason) => {
+                            send_message(
+                                super::messages::GameToClient::NameRejected(reason),
+                                client_interface,
+                            );
+                            send_message(super::messages::GameToClient::WaitName, client_interface);
+                        }
+                    }
+                }
+
                 // Simple forwards to the client_messages vector; ignores input from any client
                 // that isn't a player and sends a DeniedTableFull message
                 Ok(message) => {
@@ -246,13 +1211,440 @@ pub fn handle_game(
                     }
                 }
 
+                // The client handler thread ends when its client disconnects
+                Err(mpsc::TryRecvError::Disconnected)
+                    if client_interface.state != GciState::Disconnected =>
+                {
+                    println!("Client disconnected");
+                    client_interface.state = GciState::Disconnected;
+                }
+
                 _ => {}
             }
         }
 
+        // When the table has been restored, wait for every player to reconnect before continuing
+        if !seats_reconnected.is_empty() {
+            if seats_reconnected.contains(&false) {
+                client_messages.clear();
+                continue 'game_loop;
+            }
+
+            // Put the players back in order by index so that client_interfaces can be indexed by
+            // player index
+            client_interfaces.sort_by_key(|gci| gci.index.unwrap_or(num_players));
+
+            broadcast(
+                super::messages::GameToClient::SeatRoster(seat_roster(&seat_names)),
+                &mut client_interfaces,
+                &mut game_log,
+            );
+            if config.has_partners() {
+                broadcast(
+                    super::messages::GameToClient::Teams(team_roster(&config)),
+                    &mut client_interfaces,
+                    &mut game_log,
+                );
+            }
+            broadcast(table_rules(&config), &mut client_interfaces, &mut game_log);
+            broadcast(
+                score_update(&game_object, &config),
+                &mut client_interfaces,
+                &mut game_log,
+            );
+
+            // Ask each player for the input they were asked for before the server died
+            for (index, state) in seat_states.drain(..).enumerate() {
+                let request = match state {
+                    GciState::WaitingForInitialCut => {
+                        Some(super::messages::GameToClient::WaitInitialCut)
+                    }
+                    GciState::WaitingForDeal => Some(super::messages::GameToClient::WaitDeal),
+                    GciState::WaitingForBid => Some(super::messages::GameToClient::WaitBid),
+                    GciState::WaitingForDiscards => discard_request(discards_required(
+                        &config,
+                        index as u8,
+                        game_object.index_dealer,
+                    )),
+                    GciState::WaitingCutStarter => {
+                        Some(super::messages::GameToClient::WaitCutStarter)
+                    }
+                    GciState::WaitingForPlay => play_state.as_ref().map(|play| {
+                        super::messages::GameToClient::WaitPlay(play.playable(index as u8))
+                    }),
+                    GciState::WaitingForPlayScore => match play_state.as_ref() {
+                        Some(super::play::PlayState {
+                            claim: Some(super::play::Claim::Show { is_crib, .. }),
+                            ..
+                        }) => Some(show_request(&game_object, index as u8, *is_crib)),
+                        _ => Some(super::messages::GameToClient::WaitPlayScore),
+                    },
+                    GciState::WaitingNibs => Some(super::messages::GameToClient::WaitNibs),
+                    _ => None,
+                };
+
+                client_interfaces[index].state = state;
+                if let Some(request) = request {
+                    send_message(request, &mut client_interfaces[index]);
+                }
+            }
+
+            seats_reconnected.clear();
+            println!("All players reconnected");
+        }
+
         // Deal with clients depending on the state of the game and the input received and set the
         // output variable to the sclient_interfaces[input.index as usize].state == GciState::WaitingNametatus message this processing dictates
         output = match game_object.state {
+            // The game object only ends the game when a player reaches 121 points, but partners
+            // pool their points, shorter games are played to fewer points, and the captain needs
+            // fewer points than the team, so a side can reach the points to win first; end the game
+            // for it
+            _ if game_object.state != cribbage::GameState::End
+                && winning_side(
+                    &side_scores(&game_object, &config),
+                    &config,
+                    effective_dealer(&game_object, crib_owner),
+                )
+                .is_some() =>
+            {
+                game_object.state = cribbage::GameState::End;
+                play_state = None;
+                Ok("Side reached the points to win")
+            }
+
+            // From the cut of the starter until the show the table runs the play itself; each card
+            // played is pegged and announced, then the next player who can play is asked for a
+            // card. Once the play is over the hands are shown and the deal passes to the left.
+            // With manual scoring each player claims the points of the cards they play and of the
+            // hands they count, and with underpegging the dealer must call nibs themselves
+            _ if play_state.is_some() => {
+                let mut play = play_state.take().unwrap();
+                let mut is_over = false;
+
+                for input in &client_messages {
+                    let seat = input.index;
+
+                    // Whether the card the player played has been pegged and the play moves on
+                    let mut is_card_pegged = false;
+
+                    match (
+                        client_interfaces[seat as usize].state.clone(),
+                        &input.message,
+                        play.claim.clone(),
+                    ) {
+                        (
+                            GciState::WaitingForPlay,
+                            super::messages::ClientToGame::PlayTurn(Some(index)),
+                            _,
+                        ) => match play.play(seat, *index) {
+                            Ok((card, points)) if man_scoring => {
+                                play.claim = Some(super::play::Claim::Card {
+                                    seat: seat,
+                                    card: card,
+                                    points: points,
+                                });
+                                client_interfaces[seat as usize].state =
+                                    GciState::WaitingForPlayScore;
+                                send_message(
+                                    super::messages::GameToClient::WaitPlayScore,
+                                    &mut client_interfaces[seat as usize],
+                                );
+                            }
+                            Ok((card, points)) => {
+                                client_interfaces[seat as usize].state = GciState::WaitingForServer;
+                                peg_card(
+                                    &mut game_object,
+                                    &config,
+                                    &mut client_interfaces,
+                                    &mut game_log,
+                                    &mut play,
+                                    seat,
+                                    card,
+                                    points,
+                                    0,
+                                );
+                                is_card_pegged = true;
+                            }
+                            Err(e) => {
+                                send_message(
+                                    super::messages::GameToClient::Error(e),
+                                    &mut client_interfaces[seat as usize],
+                                );
+                                send_message(
+                                    super::messages::GameToClient::WaitPlay(play.playable(seat)),
+                                    &mut client_interfaces[seat as usize],
+                                );
+                            }
+                        },
+                        (GciState::WaitingForPlay, _, _) => {
+                            send_message(
+                                super::messages::GameToClient::Error(
+                                    "Play one of the listed cards".to_string(),
+                                ),
+                                &mut client_interfaces[seat as usize],
+                            );
+                            send_message(
+                                super::messages::GameToClient::WaitPlay(play.playable(seat)),
+                                &mut client_interfaces[seat as usize],
+                            );
+                        }
+
+                        // The points claimed for a card are checked against the points it made
+                        (
+                            GciState::WaitingForPlayScore,
+                            super::messages::ClientToGame::PlayScore(claims),
+                            Some(super::play::Claim::Card { card, points, .. }),
+                        ) => {
+                            let claimed = score_points(claims);
+                            let right = std::cmp::min(claimed, points);
+                            match settle_claim(&config, right, claimed - right, points - right) {
+                                Ok((pegged, missed)) => {
+                                    play.claim = None;
+                                    client_interfaces[seat as usize].state =
+                                        GciState::WaitingForServer;
+                                    peg_card(
+                                        &mut game_object,
+                                        &config,
+                                        &mut client_interfaces,
+                                        &mut game_log,
+                                        &mut play,
+                                        seat,
+                                        card,
+                                        pegged,
+                                        missed,
+                                    );
+                                    is_card_pegged = true;
+                                }
+                                Err(rejection) => {
+                                    send_message(rejection, &mut client_interfaces[seat as usize]);
+                                    send_message(
+                                        super::messages::GameToClient::WaitPlayScore,
+                                        &mut client_interfaces[seat as usize],
+                                    );
+                                }
+                            }
+                        }
+
+                        // The scores claimed for a hand or crib are matched against the scores the
+                        // game library finds in it
+                        (
+                            GciState::WaitingForPlayScore,
+                            super::messages::ClientToGame::PlayScore(claims),
+                            Some(super::play::Claim::Show { is_crib, .. }),
+                        ) => {
+                            let (right, wrong, missed) = super::play::match_claims(
+                                claims.clone(),
+                                show_scores(&game_object, seat, is_crib),
+                            );
+                            match settle_claim(
+                                &config,
+                                score_points(&right),
+                                score_points(&wrong),
+                                score_points(&missed),
+                            ) {
+                                Ok((pegged, missed)) => {
+                                    play.claim = None;
+                                    client_interfaces[seat as usize].state =
+                                        GciState::WaitingForServer;
+                                    if count_show(
+                                        &mut game_object,
+                                        &config,
+                                        &mut client_interfaces,
+                                        &mut game_log,
+                                        &mut play,
+                                        &stats_store,
+                                        pegged,
+                                        missed,
+                                    ) || continue_show(
+                                        &mut game_object,
+                                        &config,
+                                        &mut client_interfaces,
+                                        &mut game_log,
+                                        &mut play,
+                                        &stats_store,
+                                    ) {
+                                        is_over = true;
+                                        break;
+                                    }
+                                }
+                                Err(rejection) => {
+                                    send_message(rejection, &mut client_interfaces[seat as usize]);
+                                    send_message(
+                                        show_request(&game_object, seat, is_crib),
+                                        &mut client_interfaces[seat as usize],
+                                    );
+                                }
+                            }
+                        }
+                        (GciState::WaitingForPlayScore, _, claim) => {
+                            send_message(
+                                super::messages::GameToClient::Error(
+                                    "Claim the scores of your count".to_string(),
+                                ),
+                                &mut client_interfaces[seat as usize],
+                            );
+                            let request = match claim {
+                                Some(super::play::Claim::Show { is_crib, .. }) => {
+                                    show_request(&game_object, seat, is_crib)
+                                }
+                                _ => super::messages::GameToClient::WaitPlayScore,
+                            };
+                            send_message(request, &mut client_interfaces[seat as usize]);
+                        }
+
+                        // The dealer pegs two for his heels when they call nibs; the points go to
+                        // muggins when they do not
+                        (GciState::WaitingNibs, message, _)
+                            if *message == super::messages::ClientToGame::Confirmation
+                                || *message == super::messages::ClientToGame::Denial =>
+                        {
+                            play.claim = None;
+                            client_interfaces[seat as usize].state = GciState::WaitingForServer;
+                            if *message == super::messages::ClientToGame::Confirmation {
+                                peg(&mut game_object, seat, 2, &mut game_log);
+                                broadcast(
+                                    super::messages::GameToClient::Nibs,
+                                    &mut client_interfaces,
+                                    &mut game_log,
+                                );
+                            } else {
+                                peg_claim(
+                                    &mut game_object,
+                                    &config,
+                                    &mut client_interfaces,
+                                    &mut game_log,
+                                    seat,
+                                    0,
+                                    2,
+                                );
+                            }
+                            broadcast(
+                                score_update(&game_object, &config),
+                                &mut client_interfaces,
+                                &mut game_log,
+                            );
+
+                            if winning_side(
+                                &side_scores(&game_object, &config),
+                                &config,
+                                play.dealer,
+                            )
+                            .is_some()
+                            {
+                                is_over = true;
+                                break;
+                            }
+
+                            let turn = play.turn;
+                            client_interfaces[turn as usize].state = GciState::WaitingForPlay;
+                            send_message(
+                                super::messages::GameToClient::WaitPlay(play.playable(turn)),
+                                &mut client_interfaces[turn as usize],
+                            );
+                        }
+                        (GciState::WaitingNibs, _, _) => {
+                            send_message(
+                                super::messages::GameToClient::Error(
+                                    "Call nibs or not".to_string(),
+                                ),
+                                &mut client_interfaces[seat as usize],
+                            );
+                            send_message(
+                                super::messages::GameToClient::WaitNibs,
+                                &mut client_interfaces[seat as usize],
+                            );
+                        }
+
+                        _ => {
+                            send_message(
+                                super::messages::GameToClient::Error(
+                                    "Input is not required from you.".to_string(),
+                                ),
+                                &mut client_interfaces[seat as usize],
+                            );
+                        }
+                    }
+
+                    if !is_card_pegged {
+                        continue;
+                    }
+
+                    let (go, next) = play.next();
+                    if let Some((player, points)) = go {
+                        peg(&mut game_object, player, points, &mut game_log);
+                        broadcast(
+                            super::messages::GameToClient::Go {
+                                player: player,
+                                points: points,
+                            },
+                            &mut client_interfaces,
+                            &mut game_log,
+                        );
+                        broadcast(
+                            score_update(&game_object, &config),
+                            &mut client_interfaces,
+                            &mut game_log,
+                        );
+                    }
+
+                    // A side that pegs out ends the game without the rest of the play or the show
+                    if winning_side(&side_scores(&game_object, &config), &config, play.dealer)
+                        .is_some()
+                    {
+                        is_over = true;
+                        break;
+                    }
+
+                    match next {
+                        Some(turn) => {
+                            client_interfaces[turn as usize].state = GciState::WaitingForPlay;
+                            send_message(
+                                super::messages::GameToClient::WaitPlay(play.playable(turn)),
+                                &mut client_interfaces[turn as usize],
+                            );
+                        }
+                        None => {
+                            if show_hands(
+                                &mut game_object,
+                                &config,
+                                &mut client_interfaces,
+                                &mut game_log,
+                                &mut play,
+                                &stats_store,
+                            ) {
+                                is_over = true;
+                            }
+                            break;
+                        }
+                    }
+                }
+
+                // Once the hand is over the deal passes to the seat after the dealer, unless a
+                // side reached the points to win
+                if is_over {
+                    if winning_side(&side_scores(&game_object, &config), &config, play.dealer)
+                        .is_none()
+                    {
+                        let dealer = (game_object.index_dealer + 1) % num_players;
+                        log_entry(&mut game_log, super::log::LogEntry::NextHand(dealer));
+                        super::log::next_hand(&mut game_object, dealer);
+                        crib_owner = None;
+
+                        input_tracker = None;
+                        client_interfaces[dealer as usize].state = GciState::WaitingForDeal;
+                        send_message(
+                            super::messages::GameToClient::WaitDeal,
+                            &mut client_interfaces[dealer as usize],
+                        );
+                    }
+                    Ok("Play and show finished")
+                } else {
+                    play_state = Some(play);
+                    Ok("Polling for PlayTurn")
+                }
+            }
+
             // If the GameState is GameStart, accept player name messages until the number of
             // connected players equals the number of players in the game and all players are
             // waiting. When both conditions are true, process the GameSetup event
@@ -261,61 +1653,132 @@ pub fn handle_game(
                     || !are_all_players_waiting(&client_interfaces)
                 {
                     for input in &client_messages {
-                        // If the message reveived is a Name message and the input is from a client
-                        // who's state is WaitingName process the name; if the state is not
-                        // WaitingName or the message is not a Name, send an error or another
-                        // request for the client to send their name
-                        if let super::messages::ClientToGame::Name(name) = &input.message {
-                            if client_interfaces[input.index as usize].state
-                                == GciState::WaitingName
+                        let state = &client_interfaces[input.index as usize].state;
+
+                        // The name the player asks to sit under; a Name message is only accepted
+                        // from a client who's state is WaitingName and a LoggedIn message, sent
+                        // by the client handler once the player has logged in, from a client
+                        // who's state is WaitingLogin. A name registered to an account may only
+                        // be sat under by logging in to it. Any error is the reason the name is
+                        // rejected
+                        let requested_name = match &input.message {
+                            super::messages::ClientToGame::Name(name)
+                                if *state == GciState::WaitingName =>
+                            {
+                                Some(check_unregistered(name.trim(), &config.accounts_path))
+                            }
+                            super::messages::ClientToGame::LoggedIn(username)
+                                if *state == GciState::WaitingLogin =>
                             {
+                                Some(Ok(username.clone()))
+                            }
+                            _ => None,
+                        };
+
+                        // The message to send to ask the client for their name or login again
+                        let request = if *state == GciState::WaitingLogin {
+                            super::messages::GameToClient::WaitLogin
+                        } else {
+                            super::messages::GameToClient::WaitName
+                        };
+
+                        match requested_name {
+                            // If the name belongs to an account, tell the client and ask again
+                            Some(Err(reason)) => {
+                                send_message(
+                                    super::messages::GameToClient::NameRejected(reason),
+                                    &mut client_interfaces[input.index as usize],
+                                );
+                                send_message(request, &mut client_interfaces[input.index as usize]);
+                            }
+
+                            Some(Ok(name)) => {
+                                // Check the name is valid and not used by any other player; if
+                                // it isn't, tell the client why and ask for another name
+                                let validity = if let InputStore::Names(names) = &input_store {
+                                    validate_name(&name, input.index, names)
+                                } else {
+                                    output = Err("InputStore not Names");
+                                    break 'game_loop;
+                                };
+                                let validity = validity.and_then(|_| {
+                                    if config.reserved_seats.is_empty()
+                                        || config.reserved_seats.iter().any(|reserved| {
+                                            reserved.to_lowercase() == name.to_lowercase()
+                                        })
+                                    {
+                                        Ok(())
+                                    } else {
+                                        Err("The seats at this table are reserved".to_string())
+                                    }
+                                });
+                                if let Err(reason) = validity {
+                                    send_message(
+                                        super::messages::GameToClient::NameRejected(reason),
+                                        &mut client_interfaces[input.index as usize],
+                                    );
+                                    send_message(
+                                        request,
+                                        &mut client_interfaces[input.index as usize],
+                                    );
+                                    continue;
+                                }
+
+                                // Bind the client to the account they logged in to or registered
+                                if let super::messages::ClientToGame::LoggedIn(_) = &input.message {
+                                    client_interfaces[input.index as usize].account =
+                                        Some(name.clone());
+                                }
+
                                 // Set the correct element of names to the received name, change
                                 // the client's state, and announce the player joining to all
                                 // clients
                                 if let InputStore::Names(names) = &mut input_store {
-                                    names[input.index as usize] = name.to_string();
-                                } else {
-                                    output = Err("InputStore not Names");
-                                    break 'game_loop;
+                                    names[input.index as usize] = name.clone();
                                 }
                                 client_interfaces[input.index as usize].state =
                                     GciState::WaitingForServer;
                                 println!("Sending PlayerJoinNotification");
-                                for client_interface in &mut client_interfaces {
-                                    send_message(
-                                        super::messages::GameToClient::PlayerJoinNotification {
-                                            name: name.to_string(),
-                                            number: input.index + 1,
-                                            of: num_players,
-                                        },
-                                        client_interface,
-                                    )
-                                }
-                            } else {
+                                broadcast(
+                                    super::messages::GameToClient::PlayerJoinNotification {
+                                        name: name,
+                                        number: input.index + 1,
+                                        of: num_players,
+                                    },
+                                    &mut client_interfaces,
+                                    &mut game_log,
+                                );
+
+                                // Give the player the token that lets them take the seat back
+                                // if the table has to be restored
+                                seat_tokens[input.index as usize] = reconnect_token();
                                 send_message(
-                                    super::messages::GameToClient::Error(
-                                        "Input is not required".to_string(),
+                                    super::messages::GameToClient::ReconnectToken(
+                                        seat_tokens[input.index as usize].clone(),
                                     ),
                                     &mut client_interfaces[input.index as usize],
-                                )
+                                );
+                            }
+
+                            // If the message received is not a name or login and one is required,
+                            // ask for it again; if it is not required send an error
+                            None => {
+                                if *state == GciState::WaitingName
+                                    || *state == GciState::WaitingLogin
+                                {
+                                    send_message(
+                                        request,
+                                        &mut client_interfaces[input.index as usize],
+                                    )
+                                } else {
+                                    send_message(
+                                        super::messages::GameToClient::Error(
+                                            "Input is not required".to_string(),
+                                        ),
+                                        &mut client_interfaces[input.index as usize],
+                                    )
+                                }
                             }
-                        }
-                        // If the message received is not a Name message and one is required, send
-                        // the WaitName message; if it is not required send an error
-                        else if client_interfaces[input.index as usize].state
-                            == GciState::WaitingName
-                        {
-                            send_message(
-                                super::messages::GameToClient::WaitName,
-                                &mut client_interfaces[input.index as usize],
-                            )
-                        } else {
-                            send_message(
-                                super::messages::GameToClient::Error(
-                                    "Input is not required".to_string(),
-                                ),
-                                &mut client_interfaces[input.index as usize],
-                            )
                         }
                     }
 
@@ -325,15 +1788,34 @@ pub fn handle_game(
                 // server, process the GameSetup event to proceed to CutInitial
                 else {
                     if let InputStore::Names(names) = &input_store {
-                        game_object
-                            .process_event(cribbage::GameEvent::GameSetup {
-                                input_player_names: names.clone(),
-                                input_manual: man_scoring,
-                                input_underscoring: underpegging,
-                                input_muggins: muggins,
-                                input_overscoring: overpegging,
-                            })
-                            .unwrap();
+                        broadcast(
+                            super::messages::GameToClient::SeatRoster(seat_roster(names)),
+                            &mut client_interfaces,
+                            &mut game_log,
+                        );
+                        if config.has_partners() {
+                            broadcast(
+                                super::messages::GameToClient::Teams(team_roster(&config)),
+                                &mut client_interfaces,
+                                &mut game_log,
+                            );
+                        }
+                        broadcast(table_rules(&config), &mut client_interfaces, &mut game_log);
+                        if let Err(e) = process_event(
+                            &mut game_object,
+                            super::log::LoggedEvent::GameSetup {
+                                names: names.clone(),
+                                manual: man_scoring,
+                                underscoring: underpegging,
+                                muggins: muggins,
+                                overscoring: overpegging,
+                            },
+                            &mut game_log,
+                        ) {
+                            println!("{}", e);
+                            output = Err("Game object rejected an event");
+                            break 'game_loop;
+                        }
                     } else {
                         output = Err("InputStore not Names");
                         break 'game_loop;
@@ -357,9 +1839,16 @@ pub fn handle_game(
                     // input_tracker to receive Confirmations from each player in order by index
                     // and send a message asking for confirmation to index 0
                     None => {
-                        game_object
-                            .process_event(cribbage::GameEvent::Confirmation)
-                            .unwrap();
+                        stack_deck(&mut game_object, &mut deck_shuffler, &mut game_log);
+                        if let Err(e) = process_event(
+                            &mut game_object,
+                            super::log::LoggedEvent::Confirmation,
+                            &mut game_log,
+                        ) {
+                            println!("{}", e);
+                            output = Err("Game object rejected an event");
+                            break 'game_loop;
+                        }
 
                         input_tracker = Some(OrderedInputTracker {
                             index_active: 0,
@@ -384,12 +1873,11 @@ pub fn handle_game(
                         if ordered_input_tracker.index_last.is_some()
                             && ordered_input_tracker.index_last == ordered_input_tracker.index_stop
                         {
-                            for client_interface in &mut client_interfaces {
-                                send_message(
-                                    super::messages::GameToClient::InitialCutFailure,
-                                    client_interface,
-                                )
-                            }
+                            broadcast(
+                                super::messages::GameToClient::InitialCutFailure,
+                                &mut client_interfaces,
+                                &mut game_log,
+                            );
                             input_tracker = None;
                             Ok("All Confirmations received, input_tracker reset")
                         } else {
@@ -429,12 +1917,15 @@ pub fn handle_game(
                                         if input.message
                                             == super::messages::ClientToGame::Confirmation
                                         {
-                                            for client_interface in &mut client_interfaces {
-                                                send_message( super::messages::GameToClient::InitialCutResult{
-                                                    name: game_object.players[input.index as usize].username.clone(),
-                                                    card: game_object.players[input.index as usize].hand[0],
-                                                }, client_interface)
-                                            }
+                                            broadcast(
+                                                super::messages::GameToClient::InitialCutResult {
+                                                    player: input.index,
+                                                    card: game_object.players[input.index as usize]
+                                                        .hand[0],
+                                                },
+                                                &mut client_interfaces,
+                                                &mut game_log,
+                                            );
 
                                             client_interfaces[input.index as usize].state =
                                                 GciState::WaitingForServer;
@@ -483,16 +1974,20 @@ pub fn handle_game(
                         if ordered_input_tracker.index_last.is_some()
                             && ordered_input_tracker.index_last == ordered_input_tracker.index_stop
                         {
-                            for client_interface in &mut client_interfaces {
-                                send_message(
-                                    super::messages::GameToClient::InitialCutSuccess(
-                                        game_object.players[game_object.index_dealer as usize]
-                                            .username
-                                            .clone(),
-                                    ),
-                                    client_interface,
-                                )
-                            }
+                            broadcast(
+                                super::messages::GameToClient::InitialCutSuccess(
+                                    game_object.index_dealer,
+                                ),
+                                &mut client_interfaces,
+                                &mut game_log,
+                            );
+                            match_state.first_dealer = game_object.index_dealer;
+                            give_last(
+                                &mut game_object,
+                                &config,
+                                &mut client_interfaces,
+                                &mut game_log,
+                            );
 
                             client_interfaces[game_object.index_dealer as usize].state =
                                 GciState::WaitingForDeal;
@@ -540,12 +2035,15 @@ pub fn handle_game(
                                         if input.message
                                             == super::messages::ClientToGame::Confirmation
                                         {
-                                            for client_interface in &mut client_interfaces {
-                                                send_message(super::messages::GameToClient::InitialCutResult{
-                                                    name: game_object.players[input.index as usize].username.clone(),
-                                                    card: game_object.players[input.index as usize].hand[0],
-                                                }, client_interface)
-                                            }
+                                            broadcast(
+                                                super::messages::GameToClient::InitialCutResult {
+                                                    player: input.index,
+                                                    card: game_object.players[input.index as usize]
+                                                        .hand[0],
+                                                },
+                                                &mut client_interfaces,
+                                                &mut game_log,
+                                            );
 
                                             client_interfaces[input.index as usize].state =
                                                 GciState::WaitingForServer;
@@ -585,47 +2083,100 @@ pub fn handle_game(
                             if input.index == game_object.index_dealer {
                                 if input.message == super::messages::ClientToGame::Confirmation {
                                     // Process game through deal
-                                    game_object
-                                        .process_event(cribbage::GameEvent::Confirmation)
-                                        .unwrap();
+                                    stack_deck(&mut game_object, &mut deck_shuffler, &mut game_log);
+                                    if let Err(e) = process_event(
+                                        &mut game_object,
+                                        super::log::LoggedEvent::Confirmation,
+                                        &mut game_log,
+                                    ) {
+                                        println!("{}", e);
+                                        output = Err("Game object rejected an event");
+                                        break 'game_loop;
+                                    }
+                                    // The game object deals six cards to two players; five-card
+                                    // cribbage deals five
+                                    if config.five_card {
+                                        log_entry(
+                                            &mut game_log,
+                                            super::log::LogEntry::TrimHands(5),
+                                        );
+                                        super::log::trim_hands(&mut game_object, 5);
+                                    }
                                     // Report unsorted hands
                                     send_hands(&game_object, &mut client_interfaces);
                                     // Process game through sort
-                                    game_object
-                                        .process_event(cribbage::GameEvent::Confirmation)
-                                        .unwrap();
+                                    if let Err(e) = process_event(
+                                        &mut game_object,
+                                        super::log::LoggedEvent::Confirmation,
+                                        &mut game_log,
+                                    ) {
+                                        println!("{}", e);
+                                        output = Err("Game object rejected an event");
+                                        break 'game_loop;
+                                    }
                                     // Report sorted hands
                                     send_hands(&game_object, &mut client_interfaces);
 
-                                    // Set up clients for discard selection
-                                    for client_interface in &mut client_interfaces {
-                                        input_store = InputStore::Discards(Vec::new());
+                                    // Check every hand was dealt the right number of cards as
+                                    // the discards asked for are worked out from the hands
+                                    for seat in 0..num_players {
+                                        let expected = dealt_hand_size(
+                                            &config,
+                                            seat,
+                                            game_object.index_dealer,
+                                        );
+                                        let dealt = game_object.players[seat as usize].hand.len();
+                                        if dealt != expected {
+                                            println!(
+                                                "Player {} was dealt {} cards instead of {}",
+                                                seat, dealt, expected
+                                            );
+                                            output = Err("Hands were dealt the wrong size");
+                                            break 'game_loop;
+                                        }
+                                    }
+                                    if game_object.crib.len() != kitty_size(&config) {
+                                        println!(
+                                            "Kitty was dealt {} cards instead of {}",
+                                            game_object.crib.len(),
+                                            kitty_size(&config)
+                                        );
+                                        output = Err("Kitty was dealt the wrong size");
+                                        break 'game_loop;
+                                    }
 
-                                        if num_players != 5
-                                            || client_interface.index
-                                                != Some(game_object.index_dealer)
-                                        {
-                                            client_interface.state = GciState::WaitingForDiscards;
+                                    // With three players the dealer deals a kitty card straight
+                                    // to the crib; announce it without revealing it
+                                    if !game_object.crib.is_empty() {
+                                        broadcast(
+                                            super::messages::GameToClient::KittyDealt(
+                                                game_object.crib.len() as u8,
+                                            ),
+                                            &mut client_interfaces,
+                                            &mut game_log,
+                                        );
+                                    }
 
-                                            // Ask for two discards when there are two players and
-                                            // one discard when there are three or more players
-                                            if num_players == 2 {
+                                    // In Auction cribbage the players bid for the crib before
+                                    // discarding, otherwise set up clients for discard selection
+                                    if config.auction {
+                                        input_store = InputStore::Bids(Vec::new());
+                                        for client_interface in &mut client_interfaces {
+                                            if client_interface.index.is_some() {
+                                                client_interface.state = GciState::WaitingForBid;
                                                 send_message(
-                                                    super::messages::GameToClient::WaitDiscardTwo,
-                                                    client_interface,
-                                                );
-                                            } else {
-                                                send_message(
-                                                    super::messages::GameToClient::WaitDiscardOne,
+                                                    super::messages::GameToClient::WaitBid,
                                                     client_interface,
                                                 );
                                             }
                                         }
-                                        // If there are five players and the client is the dealer,
-                                        // they do not discard a card
-                                        else {
-                                            client_interface.state = GciState::WaitingForServer;
-                                        }
+                                    } else {
+                                        input_store = InputStore::Discards(Vec::new());
+                                        request_discards(
+                                            &game_object,
+                                            &config,
+                                            &mut client_interfaces,
+                                        );
                                     }
                                 }
                                 // If the dealer sends a message other than confirmation, resend
@@ -641,21 +2192,115 @@ pub fn handle_game(
                             // dealer
                             else {
                                 send_message(
-                                    super::messages::GameToClient::Error(
-                                        "Input is not required form you".to_string(),
+                                    super::messages::GameToClient::Error(
+                                        "Input is not required form you".to_string(),
+                                    ),
+                                    &mut client_interfaces[input.index as usize],
+                                );
+                            }
+                        }
+
+                        Ok("Waiting for confirmation from dealer")
+                    }
+                }
+            }
+
+            // In Auction cribbage the game object is in Discard while the players bid for the crib.
+            // Once every bid is in, the winner has their bid taken from their score and becomes
+            // the dealer in effect for the hand, then the players are asked for their discards.
+            // The player who dealt is still dealt their own hand size, and the deal passes from
+            // them after the show
+            cribbage::GameState::Discard if matches!(input_store, InputStore::Bids(_)) => {
+                if are_all_players_waiting(&client_interfaces) {
+                    let (winner, bid) = match &input_store {
+                        InputStore::Bids(bids) => {
+                            auction_winner(bids, game_object.index_dealer, num_players)
+                        }
+                        _ => (game_object.index_dealer, 0),
+                    };
+
+                    log_entry(
+                        &mut game_log,
+                        super::log::LogEntry::Deduction {
+                            player: winner,
+                            points: bid,
+                        },
+                    );
+                    super::log::take_points(&mut game_object, winner, bid);
+                    log_entry(&mut game_log, super::log::LogEntry::CribOwner(winner));
+                    crib_owner = Some(winner);
+
+                    broadcast(
+                        super::messages::GameToClient::AuctionResult {
+                            player: winner,
+                            bid: bid,
+                        },
+                        &mut client_interfaces,
+                        &mut game_log,
+                    );
+                    broadcast(
+                        score_update(&game_object, &config),
+                        &mut client_interfaces,
+                        &mut game_log,
+                    );
+
+                    input_store = InputStore::Discards(Vec::new());
+                    request_discards(&game_object, &config, &mut client_interfaces);
+
+                    Ok("Auction for the crib decided")
+                } else {
+                    for input in &client_messages {
+                        if client_interfaces[input.index as usize].state != GciState::WaitingForBid
+                        {
+                            send_message(
+                                super::messages::GameToClient::Error(
+                                    "Input is not required from you.".to_string(),
+                                ),
+                                &mut client_interfaces[input.index as usize],
+                            );
+                            continue;
+                        }
+
+                        match input.message {
+                            // A player can only bid points they have
+                            super::messages::ClientToGame::Bid(bid)
+                                if bid > game_object.players[input.index as usize].score =>
+                            {
+                                send_message(
+                                    super::messages::GameToClient::BidRejected(
+                                        "You can not bid more points than you have".to_string(),
                                     ),
                                     &mut client_interfaces[input.index as usize],
                                 );
+                                send_message(
+                                    super::messages::GameToClient::WaitBid,
+                                    &mut client_interfaces[input.index as usize],
+                                );
+                            }
+                            super::messages::ClientToGame::Bid(bid) => {
+                                if let InputStore::Bids(bids) = &mut input_store {
+                                    bids.push((input.index, bid));
+                                }
+                                broadcast(
+                                    super::messages::GameToClient::BidPlaced(input.index),
+                                    &mut client_interfaces,
+                                    &mut game_log,
+                                );
+                                client_interfaces[input.index as usize].state =
+                                    GciState::WaitingForServer;
                             }
+                            _ => send_message(
+                                super::messages::GameToClient::WaitBid,
+                                &mut client_interfaces[input.index as usize],
+                            ),
                         }
-
-                        Ok("Waiting for confirmation from dealer")
                     }
+                    Ok("Polling for Bid messages")
                 }
             }
 
-            // If the GameState is Discard, then the game is waiting for DiscardTwo (when two
-            // players) or DiscardOne (three to five players) messages.
+            // If the GameState is Discard, then the game is waiting for DiscardTwo (from players
+            // dealt six cards) or DiscardOne (from players dealt five cards) messages.
             cribbage::GameState::Discard => {
                 // If all players are waiting, construct the DiscardSelection event from the
                 // input_store and progress the game
@@ -697,14 +2342,43 @@ pub fn handle_game(
                         }
                     }
 
-                    game_object
-                        .process_event(cribbage::GameEvent::DiscardSelection(discards))
-                        .unwrap();
+                    // The kitty and the discards must make a crib of the size the table is played
+                    // with, and no card can be in it twice
+                    let expected = crib_size(&config, game_object.index_dealer);
+                    let mut crib = game_object.crib.clone();
+                    for discard in &discards {
+                        crib.extend(discard.iter().cloned());
+                    }
+                    if crib.len() != expected {
+                        println!(
+                            "Crib would have {} cards instead of {}",
+                            crib.len(),
+                            expected
+                        );
+                        output = Err("Crib does not have the right number of cards");
+                        break 'game_loop;
+                    }
+                    if has_duplicates(&crib) {
+                        output = Err("Crib has the same card more than once");
+                        break 'game_loop;
+                    }
 
-                    for client_interface in &mut client_interfaces {
-                        send_message(super::messages::GameToClient::AllDiscards, client_interface);
+                    if let Err(e) = process_event(
+                        &mut game_object,
+                        super::log::LoggedEvent::DiscardSelection(discards),
+                        &mut game_log,
+                    ) {
+                        println!("{}", e);
+                        output = Err("Game object rejected an event");
+                        break 'game_loop;
                     }
 
+                    broadcast(
+                        super::messages::GameToClient::AllDiscards,
+                        &mut client_interfaces,
+                        &mut game_log,
+                    );
+
                     // Prepares game for CutStarter
                     client_interfaces
                         [(game_object.index_dealer as usize + 1) % num_players as usize]
@@ -722,14 +2396,70 @@ pub fn handle_game(
                     for input in &mut client_messages {
                         if client_interfaces[input.index as usize].state
                             == GciState::WaitingForDiscards
+                            && input.message == super::messages::ClientToGame::RequestHint
                         {
-                            // Poll for DiscardTwo events when there are two players
-                            if num_players == 2 {
-                                if let super::messages::ClientToGame::DiscardTwo {
-                                    index_one,
-                                    index_two,
-                                } = input.message
-                                {
+                            // Answer a request for a hint with the ranked ways to discard and
+                            // let every other player know that a hint was used, or tell the
+                            // player that hints are disabled at the table
+                            if config.hints {
+                                let index = input.index;
+                                let hints = super::hints::discard_hints(
+                                    &game_object.players[index as usize].hand,
+                                    discards_required(&config, index, game_object.index_dealer),
+                                    crib_size(&config, game_object.index_dealer),
+                                    config.side_of(index)
+                                        == config
+                                            .side_of(effective_dealer(&game_object, crib_owner)),
+                                );
+                                send_message(
+                                    super::messages::GameToClient::DiscardHints(hints),
+                                    &mut client_interfaces[index as usize],
+                                );
+                                broadcast(
+                                    super::messages::GameToClient::HintUsed(index),
+                                    &mut client_interfaces,
+                                    &mut game_log,
+                                );
+                            } else {
+                                send_message(
+                                    super::messages::GameToClient::HintDenied(
+                                        "Hints are disabled at this table".to_string(),
+                                    ),
+                                    &mut client_interfaces[input.index as usize],
+                                );
+                            }
+                        } else if client_interfaces[input.index as usize].state
+                            == GciState::WaitingForDiscards
+                        {
+                            // Poll for DiscardTwo events from players who must discard two cards
+                            if discards_required(&config, input.index, game_object.index_dealer)
+                                == 2
+                            {
+                                // Discards that are not two different cards of the player's hand
+                                // are rejected and asked for again
+                                let checked = match input.message {
+                                    super::messages::ClientToGame::DiscardTwo {
+                                        index_one,
+                                        index_two,
+                                    } => Some(
+                                        check_discards(
+                                            &game_object.players[input.index as usize].hand,
+                                            &[index_one, index_two],
+                                        )
+                                        .map(|_| (index_one, index_two)),
+                                    ),
+                                    _ => None,
+                                };
+                                if let Some(Err(reason)) = checked {
+                                    send_message(
+                                        super::messages::GameToClient::Error(reason),
+                                        &mut client_interfaces[input.index as usize],
+                                    );
+                                    send_message(
+                                        super::messages::GameToClient::WaitDiscardTwo,
+                                        &mut client_interfaces[input.index as usize],
+                                    );
+                                } else if let Some(Ok((index_one, index_two))) = checked {
                                     // Add DiscardSelection to input_store
                                     if let InputStore::Discards(discards) = &mut input_store {
                                         discards.push(DiscardSelection::TwoDiscard {
@@ -745,16 +2475,13 @@ pub fn handle_game(
                                     }
 
                                     // Announce that the discards were placed
-                                    for client_interface in &mut client_interfaces {
-                                        send_message(
-                                            super::messages::GameToClient::DiscardPlacedTwo(
-                                                game_object.players[input.index as usize]
-                                                    .username
-                                                    .clone(),
-                                            ),
-                                            client_interface,
-                                        );
-                                    }
+                                    broadcast(
+                                        super::messages::GameToClient::DiscardPlacedTwo(
+                                            input.index,
+                                        ),
+                                        &mut client_interfaces,
+                                        &mut game_log,
+                                    );
 
                                     // Change the player's state to WaitingForServer
                                     client_interfaces[input.index as usize].state =
@@ -769,11 +2496,30 @@ pub fn handle_game(
                                     );
                                 }
                             }
-                            // Poll for DiscardOne events when there are three to five players
+                            // Poll for DiscardOne events from players who must discard one card
                             else {
-                                if let super::messages::ClientToGame::DiscardOne { index } =
-                                    input.message
-                                {
+                                // A discard that is not a card of the player's hand is rejected and
+                                // asked for again
+                                let checked = match input.message {
+                                    super::messages::ClientToGame::DiscardOne { index } => Some(
+                                        check_discards(
+                                            &game_object.players[input.index as usize].hand,
+                                            &[index],
+                                        )
+                                        .map(|_| index),
+                                    ),
+                                    _ => None,
+                                };
+                                if let Some(Err(reason)) = checked {
+                                    send_message(
+                                        super::messages::GameToClient::Error(reason),
+                                        &mut client_interfaces[input.index as usize],
+                                    );
+                                    send_message(
+                                        super::messages::GameToClient::WaitDiscardOne,
+                                        &mut client_interfaces[input.index as usize],
+                                    );
+                                } else if let Some(Ok(index)) = checked {
                                     // Add DiscardSelection to input_store
                                     if let InputStore::Discards(discards) = &mut input_store {
                                         discards.push(DiscardSelection::OneDiscard {
@@ -788,16 +2534,13 @@ pub fn handle_game(
                                     }
 
                                     // Announce that the discards were placed
-                                    for client_interface in &mut client_interfaces {
-                                        send_message(
-                                            super::messages::GameToClient::DiscardPlacedOne(
-                                                game_object.players[input.index as usize]
-                                                    .username
-                                                    .clone(),
-                                            ),
-                                            client_interface,
-                                        );
-                                    }
+                                    broadcast(
+                                        super::messages::GameToClient::DiscardPlacedOne(
+                                            input.index,
+                                        ),
+                                        &mut client_interfaces,
+                                        &mut game_log,
+                                    );
 
                                     // Change the player's state to WaitingForServer
                                     client_interfaces[input.index as usize].state =
@@ -828,33 +2571,100 @@ pub fn handle_game(
             // player with the index immediately after the dealer to cut the starter card from the
             // deck
             cribbage::GameState::CutStarter => {
-                // If all players are waiting then the confirmation has been received and the game
-                // is ready to progress to NibsCheck or PlayWaitForCard depending on if manual
-                // scoring and underpegging are enabled
+                // If all players are waiting then the confirmation has been received and the play
+                // can start
                 if are_all_players_waiting(&client_interfaces) {
-                    game_object
-                        .process_event(cribbage::GameEvent::Confirmation)
-                        .unwrap();
-                    for client_interface in &mut client_interfaces {
-                        println!("Sending CutStarter");
-                        send_message(
-                            super::messages::GameToClient::CutStarter(
-                                game_object.players[(game_object.index_dealer as usize + 1)
-                                    % num_players as usize]
-                                    .username
-                                    .clone(),
-                                game_object.starter_card,
-                            ),
-                            client_interface,
+                    let dealer = effective_dealer(&game_object, crib_owner);
+                    let dealt_by = game_object.index_dealer;
+                    let dealt_by_score = game_object.players[dealt_by as usize].score;
+                    if let Err(e) = process_event(
+                        &mut game_object,
+                        super::log::LoggedEvent::Confirmation,
+                        &mut game_log,
+                    ) {
+                        println!("{}", e);
+                        output = Err("Game object rejected an event");
+                        break 'game_loop;
+                    }
+                    println!("Sending CutStarter");
+                    broadcast(
+                        super::messages::GameToClient::CutStarter(
+                            (game_object.index_dealer + 1) % num_players,
+                            game_object.starter_card,
+                        ),
+                        &mut client_interfaces,
+                        &mut game_log,
+                    );
+
+                    let hands = game_object
+                        .players
+                        .iter()
+                        .map(|player| player.hand.clone())
+                        .collect();
+                    let mut play = super::play::PlayState::new(hands, dealer, config.five_card);
+
+                    // Two for his heels when the starter is a jack, unless the game object gave
+                    // the dealer the points itself. With manual scoring and underpegging the
+                    // dealer must call nibs before the play starts. The game object gives the
+                    // points to the player who dealt, so they are taken back when the winner of
+                    // the auction deals in effect or the dealer must call nibs
+                    let is_heels =
+                        game_object.starter_card.value == cribbage::deck::CardValue::Jack;
+                    let is_called = is_heels && man_scoring && underpegging;
+                    let mut is_given =
+                        game_object.players[dealt_by as usize].score != dealt_by_score;
+                    if is_given && (dealer != dealt_by || is_called) {
+                        let given = game_object.players[dealt_by as usize]
+                            .score
+                            .saturating_sub(dealt_by_score);
+                        log_entry(
+                            &mut game_log,
+                            super::log::LogEntry::Deduction {
+                                player: dealt_by,
+                                points: given,
+                            },
+                        );
+                        super::log::take_points(&mut game_object, dealt_by, given);
+                        is_given = false;
+                    }
+                    if is_heels && !is_called {
+                        if !is_given {
+                            peg(&mut game_object, dealer, 2, &mut game_log);
+                        }
+                        broadcast(
+                            super::messages::GameToClient::Nibs,
+                            &mut client_interfaces,
+                            &mut game_log,
+                        );
+                        broadcast(
+                            score_update(&game_object, &config),
+                            &mut client_interfaces,
+                            &mut game_log,
                         );
                     }
 
-                    if underpegging {
-                        // Prepare for NibsCheck
-                        Err("TODO")
+                    if is_called {
+                        play.claim = Some(super::play::Claim::Heels { seat: dealer });
+                        client_interfaces[dealer as usize].state = GciState::WaitingNibs;
+                        send_message(
+                            super::messages::GameToClient::WaitNibs,
+                            &mut client_interfaces[dealer as usize],
+                        );
+                        play_state = Some(play);
+                        Ok("Waiting for the dealer to call nibs")
+                    } else if winning_side(&side_scores(&game_object, &config), &config, dealer)
+                        .is_some()
+                    {
+                        Ok("Dealer reached the points to win with the starter")
                     } else {
-                        // Prepare for PlayWaitForCard
-                        Err("TODO")
+                        let turn = play.turn;
+                        client_interfaces[turn as usize].state = GciState::WaitingForPlay;
+                        send_message(
+                            super::messages::GameToClient::WaitPlay(play.playable(turn)),
+                            &mut client_interfaces[turn as usize],
+                        );
+                        play_state = Some(play);
+                        Ok("Starting the play")
                     }
                 }
                 // If any player is not waiting, then the confirmation has yet to be received
@@ -894,14 +2704,163 @@ pub fn handle_game(
                 }
             }
 
-            // Prepare the game to shutdown when the end state is reached
-            cribbage::GameState::End => Ok("Server ending"),
+            // Record the result of the game, then either start the next game of the match or
+            // prepare the game to shutdown when the match has been decided
+            cribbage::GameState::End => {
+                let scores = side_scores(&game_object, &config);
+                let dealer = effective_dealer(&game_object, crib_owner);
+                let winner = match winning_side(&scores, &config, dealer) {
+                    Some(winner) => winner,
+                    None => {
+                        output = Err("Game ended without a side reaching the points to win");
+                        break 'game_loop;
+                    }
+                };
+                broadcast(
+                    super::messages::GameToClient::GameWinner(winner),
+                    &mut client_interfaces,
+                    &mut game_log,
+                );
+
+                let results = seat_results(&game_object, &config, winner, &client_interfaces);
+                {
+                    let mut stats_store = stats_store.lock().unwrap();
+                    if config.ranked {
+                        stats_store.record_ratings(&results);
+                    }
+                    if let Err(e) = stats_store.record_game(&results, &skunk_lines(&config)) {
+                        println!("{}", e);
+                    }
+                }
+
+                let match_winner =
+                    match_state.record_game(winner, is_skunk(&scores, winner, &config));
+                if match_state.is_match() {
+                    broadcast(
+                        super::messages::GameToClient::MatchScore(match_state.score()),
+                        &mut client_interfaces,
+                        &mut game_log,
+                    );
+                }
+
+                match match_winner {
+                    Some(winner) => {
+                        // The player in the seat with the index of the winning side is on that side;
+                        // the tournament knows them by their account
+                        if let Some(path) = &config.result_path {
+                            let result = super::tournament::TableResult {
+                                winner: seat_account(&client_interfaces, winner).unwrap_or(
+                                    game_object.players[winner as usize].username.clone(),
+                                ),
+                            };
+                            if let Err(e) = result.save(path) {
+                                println!("{}", e);
+                            }
+                        }
+                        if match_state.is_match() {
+                            broadcast(
+                                super::messages::GameToClient::MatchWinner(winner),
+                                &mut client_interfaces,
+                                &mut game_log,
+                            );
+                        }
+                        Ok("Server ending")
+                    }
+
+                    // Set up the next game with the same seats; the deal alternates between games
+                    // so the initial cut is skipped and the dealer is asked to deal
+                    None => {
+                        let names: Vec<String> = game_object
+                            .players
+                            .iter()
+                            .map(|player| player.username.clone())
+                            .collect();
+                        let dealer = match_state.next_first_dealer();
+
+                        log_entry(&mut game_log, super::log::LogEntry::NewGame);
+                        game_object = cribbage::Game::new();
+                        if let Err(e) = process_event(
+                            &mut game_object,
+                            super::log::LoggedEvent::GameSetup {
+                                names: names,
+                                manual: man_scoring,
+                                underscoring: underpegging,
+                                muggins: muggins,
+                                overscoring: overpegging,
+                            },
+                            &mut game_log,
+                        ) {
+                            println!("{}", e);
+                            output = Err("Game object rejected an event");
+                            break 'game_loop;
+                        }
+                        log_entry(&mut game_log, super::log::LogEntry::FirstDealer(dealer));
+                        super::log::set_first_dealer(&mut game_object, dealer);
+
+                        broadcast(
+                            super::messages::GameToClient::NewGame {
+                                number: match_state.games_played + 1,
+                                dealer: dealer,
+                            },
+                            &mut client_interfaces,
+                            &mut game_log,
+                        );
+                        broadcast(
+                            score_update(&game_object, &config),
+                            &mut client_interfaces,
+                            &mut game_log,
+                        );
+                        give_last(
+                            &mut game_object,
+                            &config,
+                            &mut client_interfaces,
+                            &mut game_log,
+                        );
+
+                        input_tracker = None;
+                        crib_owner = None;
+                        client_interfaces[dealer as usize].state = GciState::WaitingForDeal;
+                        send_message(
+                            super::messages::GameToClient::WaitDeal,
+                            &mut client_interfaces[dealer as usize],
+                        );
+
+                        Ok("Started the next game of the match")
+                    }
+                }
+            }
 
             // Return an error for any
             _ => Err("Unrecognized GameState"),
         };
 
         client_messages.clear();
+
+        // A table whose log can not be written to could not be restored or replayed, so it ends
+        // instead of carrying on unrecorded
+        if game_log.has_failed() {
+            output = Err("Could not write to the game log");
+        }
+        // Save the table whenever the game has progressed; nothing is saved before the game is set
+        // up as there is no game to restore
+        else if game_object.state != cribbage::GameState::GameStart
+            && game_log.entries != snapshot_log_entries
+        {
+            save_snapshot(
+                &config,
+                &game_object,
+                &game_log,
+                &client_interfaces,
+                &input_store,
+                &input_tracker,
+                &deck_shuffler,
+                &match_state,
+                &seat_tokens,
+                &play_state,
+                crib_owner,
+            );
+            snapshot_log_entries = game_log.entries;
+        }
     }
 
     match output {
@@ -912,15 +2871,10 @@ pub fn handle_game(
         ),
     }
 
-    for client_interface in client_interfaces {
-        client_interface
-            .transmitter
-            .send(super::messages::GameToClient::Disconnect)
-            .unwrap();
-
-        assert!(
-            client_interface.receiver.recv()
-                == Ok(super::messages::ClientToGame::TransmissionReceived)
+    for mut client_interface in client_interfaces {
+        send_message(
+            super::messages::GameToClient::Disconnect,
+            &mut client_interface,
         );
     }
 
@@ -928,3 +2882,664 @@ pub fn handle_game(
         .send(super::messages::GameToMain::EndServer)
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(num_players: u8, optional: &str) -> super::super::config::TableConfig {
+        super::super::config::TableConfig::from_test_args(num_players, optional)
+    }
+
+    fn hand(cards: &str) -> Vec<cribbage::deck::Card> {
+        cards
+            .split_whitespace()
+            .map(|card| super::super::deck::parse_card(card).unwrap())
+            .collect()
+    }
+
+    // The config of a table run by run_table; its log, snapshot, and statistics are kept in the
+    // temporary directory under the given name, and its decks are stacked in order by suit then
+    // value until they run out
+    fn table_config(
+        num_players: u8,
+        optional: &str,
+        name: &str,
+    ) -> super::super::config::TableConfig {
+        let path = |extension: &str| {
+            let path = std::env::temp_dir().join(format!("cribbage_{}_test.{}", name, extension));
+            let _ = std::fs::remove_file(&path);
+            path.to_string_lossy().to_string()
+        };
+        let mut config = config(num_players, optional);
+        config.log_path = path("log");
+        config.snapshot_path = path("snapshot");
+        config.stats_path = path("json");
+        config.accounts_path = path("accounts");
+
+        let mut deck = Vec::new();
+        for suit in "CDHS".chars() {
+            for value in "A23456789TJQK".chars() {
+                deck.push(format!("{}{}", value, suit));
+            }
+        }
+        config.deck_setup = super::super::deck::DeckSetup::Stacked {
+            decks: vec![deck; 40],
+            seed: 1,
+        };
+        config
+    }
+
+    // A client that answers every request with the first choice it is given; returns the messages
+    // it was sent once it is told to disconnect. In Auction cribbage the client in the bidding
+    // seat bids one point whenever it has a point to bid and every other client bids nothing
+    fn fake_client(
+        name: String,
+        bidder: Option<u8>,
+        transmitter: mpsc::Sender<super::super::messages::ClientToGame>,
+        receiver: mpsc::Receiver<super::super::messages::GameToClient>,
+    ) -> thread::JoinHandle<(String, Vec<super::super::messages::GameToClient>)> {
+        thread::spawn(move || {
+            use super::super::messages::{ClientToGame, GameToClient};
+
+            transmitter.send(ClientToGame::Greeting).unwrap();
+            let mut received = Vec::new();
+            let mut seat = None;
+            let mut score = 0;
+            while let Ok(message) = receiver.recv() {
+                let _ = transmitter.send(ClientToGame::TransmissionReceived);
+                match &message {
+                    GameToClient::SeatRoster(roster) => {
+                        seat = roster
+                            .iter()
+                            .find(|(_, seat_name)| *seat_name == name)
+                            .map(|(seat, _)| *seat);
+                    }
+                    GameToClient::ScoreUpdate(scores) => {
+                        score = scores
+                            .iter()
+                            .find(|(player, _)| Some(*player) == seat)
+                            .map_or(0, |(_, score)| *score);
+                    }
+                    _ => {}
+                }
+                let answer = match &message {
+                    GameToClient::WaitName => Some(ClientToGame::Name(name.clone())),
+                    GameToClient::WaitBid if seat.is_some() && seat == bidder && score > 0 => {
+                        Some(ClientToGame::Bid(1))
+                    }
+                    GameToClient::WaitBid => Some(ClientToGame::Bid(0)),
+                    GameToClient::WaitInitialCut
+                    | GameToClient::WaitDeal
+                    | GameToClient::WaitCutStarter => Some(ClientToGame::Confirmation),
+                    GameToClient::WaitDiscardOne => Some(ClientToGame::DiscardOne { index: 0 }),
+                    GameToClient::WaitDiscardTwo => Some(ClientToGame::DiscardTwo {
+                        index_one: 0,
+                        index_two: 1,
+                    }),
+                    GameToClient::WaitPlay(playable) => {
+                        Some(ClientToGame::PlayTurn(Some(playable[0])))
+                    }
+                    _ => None,
+                };
+                let is_disconnect = matches!(message, GameToClient::Disconnect);
+                received.push(message);
+                if is_disconnect {
+                    break;
+                }
+                if let Some(answer) = answer {
+                    let _ = transmitter.send(answer);
+                }
+            }
+            (name, received)
+        })
+    }
+
+    // Runs a table through handle_game with a fake client in every seat until the game ends, with
+    // the given seat bidding for the crib in Auction cribbage; returns the messages sent to each
+    // seat in order by seat
+    fn run_table(
+        config: super::super::config::TableConfig,
+        bidder: Option<u8>,
+    ) -> Vec<Vec<super::super::messages::GameToClient>> {
+        let num_players = config.num_players;
+        let stats_store = super::super::stats::StatsStore::open(&config.stats_path).unwrap();
+        let (main_transmitter, game_receiver) = mpsc::channel();
+        let (game_transmitter, main_receiver) = mpsc::channel();
+        let game = thread::spawn(move || {
+            handle_game(
+                cribbage::Game::new(),
+                config,
+                None,
+                Arc::new(Mutex::new(stats_store)),
+                game_receiver,
+                game_transmitter,
+            )
+        });
+
+        let mut clients = Vec::new();
+        for seat in 0..num_players {
+            let (client_transmitter, client_receiver) = mpsc::channel();
+            let (transmitter, receiver) = mpsc::channel();
+            main_transmitter
+                .send(super::super::messages::MainToGame::NewClient {
+                    transmitter: transmitter,
+                    receiver: client_receiver,
+                })
+                .unwrap();
+            clients.push(fake_client(
+                format!("Player {}", seat),
+                bidder,
+                client_transmitter,
+                receiver,
+            ));
+        }
+
+        assert!(matches!(
+            main_receiver.recv_timeout(time::Duration::from_secs(120)),
+            Ok(super::super::messages::GameToMain::EndServer)
+        ));
+        game.join().unwrap();
+
+        // Clients are seated in the order their greetings arrive, so each finds its seat by its
+        // name in the roster
+        let mut seats: Vec<(u8, Vec<super::super::messages::GameToClient>)> = clients
+            .into_iter()
+            .map(|client| {
+                let (name, received) = client.join().unwrap();
+                let seat = received
+                    .iter()
+                    .find_map(|message| match message {
+                        super::super::messages::GameToClient::SeatRoster(roster) => roster
+                            .iter()
+                            .find(|(_, seat_name)| *seat_name == name)
+                            .map(|(seat, _)| *seat),
+                        _ => None,
+                    })
+                    .unwrap();
+                (seat, received)
+            })
+            .collect();
+        seats.sort_by_key(|(seat, _)| *seat);
+        seats.into_iter().map(|(_, received)| received).collect()
+    }
+
+    #[test]
+    fn six_players_are_dealt_five_and_discard_one() {
+        for teams in &["teams=2", "teams=3"] {
+            let config = config(6, teams);
+            for dealer in 0..6 {
+                for seat in 0..6 {
+                    assert_eq!(dealt_hand_size(&config, seat, dealer), 5);
+                    assert_eq!(discards_required(&config, seat, dealer), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn deals_make_a_crib_of_the_right_size() {
+        // The number of players and options, the cards dealt to the dealer and to everyone else,
+        // and the size of the crib
+        let tables = vec![
+            (2, "", 6, 6, 4),
+            (2, "fivecard=true", 5, 5, 4),
+            (3, "", 5, 5, 4),
+            (4, "", 5, 5, 4),
+            (4, "teams=2", 5, 5, 4),
+            (5, "", 4, 5, 4),
+            (6, "teams=2", 5, 5, 6),
+            (6, "teams=3", 5, 5, 6),
+        ];
+        for (num_players, optional, dealer_cards, other_cards, crib) in tables {
+            let config = config(num_players, optional);
+            let dealer = num_players - 1;
+            for seat in 0..num_players {
+                let expected = if seat == dealer {
+                    dealer_cards
+                } else {
+                    other_cards
+                };
+                assert_eq!(dealt_hand_size(&config, seat, dealer), expected);
+                assert_eq!(
+                    discards_required(&config, seat, dealer),
+                    expected - kept_hand_size(&config)
+                );
+            }
+            assert_eq!(
+                crib_size(&config, dealer),
+                crib,
+                "{} {}",
+                num_players,
+                optional
+            );
+        }
+    }
+
+    #[test]
+    fn discards_must_be_different_cards_of_the_hand() {
+        let dealt = hand("AC 2C 3C 4C 5C 6C");
+        assert!(check_discards(&dealt, &[0, 5]).is_ok());
+        assert!(check_discards(&dealt, &[5]).is_ok());
+        assert!(check_discards(&dealt, &[6]).is_err());
+        assert!(check_discards(&dealt, &[2, 9]).is_err());
+        assert!(check_discards(&dealt, &[3, 3]).is_err());
+    }
+
+    #[test]
+    fn crib_with_a_card_twice_is_caught() {
+        assert!(!has_duplicates(&hand("AC 2C 3C 4C")));
+        assert!(has_duplicates(&hand("AC 2C AC 4C")));
+    }
+
+    #[test]
+    fn five_player_dealer_is_never_asked_to_discard() {
+        let config = config(5, "");
+        for dealer in 0..5 {
+            for seat in 0..5 {
+                let request = discard_request(discards_required(&config, seat, dealer));
+                if seat == dealer {
+                    assert!(request.is_none());
+                } else {
+                    assert!(matches!(
+                        request,
+                        Some(super::super::messages::GameToClient::WaitDiscardOne)
+                    ));
+                }
+            }
+        }
+    }
+
+    // The dealer of five players keeps the four cards they were dealt and plays and shows them like
+    // everyone else; the play and the show both go around from the seat after the dealer
+    #[test]
+    fn five_player_dealer_plays_and_shows_last() {
+        let config = config(5, "");
+        let dealer = 2;
+        let kept = vec![
+            hand("AC 2C 3C 4C"),
+            hand("AD 2D 3D 4D"),
+            hand("AH 2H 3H 4H"),
+            hand("AS 2S 3S 4S"),
+            hand("6C 7C 8C 9C"),
+        ];
+        let mut play = super::super::play::PlayState::new(kept, dealer, config.five_card);
+
+        let mut order = Vec::new();
+        let mut turn = Some(play.turn);
+        while let Some(seat) = turn {
+            let index = play.playable(seat)[0];
+            play.play(seat, index).unwrap();
+            order.push(seat);
+            turn = play.next().1;
+        }
+        assert_eq!(&order[..5], &[3, 4, 0, 1, 2]);
+        assert_eq!(order.iter().filter(|seat| **seat == dealer).count(), 4);
+        assert_eq!(
+            super::super::play::show_order(dealer, config.num_players),
+            vec![3, 4, 0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn lowball_tie_goes_to_the_first_side_to_show() {
+        let config = config(3, "lowball=true points=61");
+
+        // Nobody has reached the points to win
+        assert_eq!(winning_side(&vec![40, 40, 60], &config, 0), None);
+
+        // The fewest points win outright whoever dealt
+        assert_eq!(winning_side(&vec![61, 20, 30], &config, 1), Some(1));
+
+        // Sides 0 and 2 are tied; side 2 shows first when seat 1 deals and side 0 when seat 2 deals
+        assert_eq!(winning_side(&vec![30, 61, 30], &config, 1), Some(2));
+        assert_eq!(winning_side(&vec![30, 61, 30], &config, 2), Some(0));
+    }
+
+    #[test]
+    fn lowball_tie_between_teams_goes_by_the_seat_after_the_dealer() {
+        let config = config(4, "lowball=true teams=2");
+        assert_eq!(winning_side(&vec![121, 50], &config, 0), Some(1));
+        assert_eq!(winning_side(&vec![121, 121], &config, 0), Some(1));
+        assert_eq!(winning_side(&vec![121, 121], &config, 3), Some(0));
+    }
+
+    #[test]
+    fn five_card_non_dealer_starts_with_three_for_last() {
+        let config = config(2, "fivecard=true");
+        assert_eq!(config.points_to_win, 61);
+        assert_eq!(points_for_last(&config, 0), Some((1, 3)));
+        assert_eq!(points_for_last(&config, 1), Some((0, 3)));
+        assert_eq!(points_for_last(&self::config(2, ""), 0), None);
+    }
+
+    // Five-card cribbage plays a single count; the play ends at the first go or thirty-one even
+    // though cards are left in the hands
+    #[test]
+    fn five_card_play_ends_at_the_first_go() {
+        let config = config(2, "fivecard=true");
+        let kept = vec![hand("TC 9C 2C"), hand("KD 4D 3D")];
+        let mut play = super::super::play::PlayState::new(kept, 1, config.five_card);
+        let mut cards = 0;
+        let mut go = None;
+        let mut turn = Some(play.turn);
+        while let Some(seat) = turn {
+            let index = play.playable(seat)[0];
+            let (_, points) = play.play(seat, index).unwrap();
+            play.peg(seat, points);
+            cards += 1;
+            let next = play.next();
+            go = next.0;
+            turn = next.1;
+        }
+
+        // Ten, king, and nine make twenty-nine; the two makes thirty-one and ends the play
+        assert_eq!(cards, 4);
+        assert!(go.is_none());
+        assert_eq!(play.pegged, vec![2, 0]);
+    }
+
+    #[test]
+    fn claims_are_settled_by_the_scoring_options() {
+        let mut config = config(2, "");
+        assert!(matches!(settle_claim(&config, 4, 0, 0), Ok((4, 0))));
+        assert!(matches!(
+            settle_claim(&config, 4, 2, 0),
+            Err(super::super::messages::GameToClient::InvalidPlayScoring)
+        ));
+        assert!(matches!(
+            settle_claim(&config, 2, 0, 2),
+            Err(super::super::messages::GameToClient::IncompletePlayScoring)
+        ));
+
+        // Points claimed wrongly stand with overpegging and points missed are lost with
+        // underpegging
+        config.overpegging = true;
+        config.underpegging = true;
+        assert!(matches!(settle_claim(&config, 4, 2, 0), Ok((6, 0))));
+        assert!(matches!(settle_claim(&config, 2, 0, 2), Ok((2, 2))));
+    }
+
+    #[test]
+    fn muggins_goes_to_the_next_opponent() {
+        assert_eq!(muggins_seat(&config(3, ""), 2), 0);
+        assert_eq!(muggins_seat(&config(4, "teams=2"), 1), 2);
+        assert_eq!(muggins_seat(&config(6, "teams=2"), 5), 0);
+        assert_eq!(muggins_seat(&config(6, "teams=3"), 0), 1);
+    }
+
+    #[test]
+    fn highest_bid_wins_the_crib() {
+        assert_eq!(auction_winner(&vec![(0, 3), (1, 5)], 0, 2), (1, 5));
+        assert_eq!(auction_winner(&vec![(2, 1), (0, 0), (1, 4)], 2, 3), (1, 4));
+    }
+
+    #[test]
+    fn tied_bids_go_to_the_first_in_turn_order_from_the_dealer() {
+        let bids = vec![(0, 4), (1, 2), (2, 4), (3, 4)];
+        assert_eq!(auction_winner(&bids, 0, 4), (0, 4));
+        assert_eq!(auction_winner(&bids, 1, 4), (2, 4));
+        assert_eq!(auction_winner(&bids, 3, 4), (3, 4));
+    }
+
+    #[test]
+    fn dealer_wins_the_crib_when_nobody_bids() {
+        let bids = vec![(0, 0), (1, 0), (2, 0)];
+        for dealer in 0..3 {
+            assert_eq!(auction_winner(&bids, dealer, 3), (dealer, 0));
+        }
+        assert_eq!(auction_winner(&Vec::new(), 1, 3), (1, 0));
+    }
+
+    #[test]
+    fn captain_and_team_need_their_own_points_to_win() {
+        let config = config(3, "captain=true");
+        assert_eq!(config.captain_seat(), Some(0));
+        assert_eq!(
+            (config.points_to_win_of(0), config.points_to_win_of(1)),
+            (61, 121)
+        );
+        assert_eq!(winning_side(&vec![60, 120], &config, 0), None);
+        assert_eq!(winning_side(&vec![61, 120], &config, 0), Some(0));
+        assert_eq!(winning_side(&vec![60, 121], &config, 0), Some(1));
+        assert_eq!(skunk_lines(&config), vec![31, 91]);
+
+        // The captain is only skunked under their own skunk line
+        assert!(!is_skunk(&vec![31, 121], 1, &config));
+        assert!(is_skunk(&vec![30, 121], 1, &config));
+        assert!(is_skunk(&vec![61, 90], 0, &config));
+    }
+
+    #[test]
+    fn captain_points_set_the_captain_skunk_line() {
+        let config = config(3, "captain=true points=91 captainpoints=40");
+        assert_eq!(
+            (config.points_to_win_of(0), config.points_to_win_of(1)),
+            (40, 91)
+        );
+        assert_eq!(skunk_lines(&config), vec![20, 61]);
+        assert_eq!(winning_side(&vec![40, 90], &config, 2), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "need captain=true")]
+    fn captain_points_need_captain() {
+        config(3, "captainpoints=40");
+    }
+
+    #[test]
+    fn discard_request_matches_the_discards_required() {
+        assert!(discard_request(0).is_none());
+        assert!(matches!(
+            discard_request(1),
+            Some(super::super::messages::GameToClient::WaitDiscardOne)
+        ));
+        assert!(matches!(
+            discard_request(2),
+            Some(super::super::messages::GameToClient::WaitDiscardTwo)
+        ));
+    }
+
+    #[test]
+    fn six_players_alternate_between_two_teams_of_three() {
+        let config = config(6, "teams=2");
+        assert_eq!(config.num_sides(), 2);
+        let sides: Vec<u8> = (0..6).map(|seat| config.side_of(seat)).collect();
+        assert_eq!(sides, vec![0, 1, 0, 1, 0, 1]);
+        assert_eq!(team_roster(&config), vec![vec![0, 2, 4], vec![1, 3, 5]]);
+    }
+
+    #[test]
+    fn six_players_rotate_between_three_teams_of_two() {
+        let config = config(6, "teams=3");
+        assert_eq!(config.num_sides(), 3);
+        let sides: Vec<u8> = (0..6).map(|seat| config.side_of(seat)).collect();
+        assert_eq!(sides, vec![0, 1, 2, 0, 1, 2]);
+        assert_eq!(
+            team_roster(&config),
+            vec![vec![0, 3], vec![1, 4], vec![2, 5]]
+        );
+    }
+
+    // Plays out a hand of six players with every player playing their first playable card, as the
+    // table would between the cut of the starter and the show, and checks that every card is played
+    // in turn and the pegged points add up to the team scores
+    #[test]
+    fn six_player_hand_plays_every_card() {
+        for teams in &["teams=2", "teams=3"] {
+            let config = config(6, teams);
+            let dealt = vec![
+                hand("AC 2C 3C 4C 5C"),
+                hand("AD 2D 3D 4D 5D"),
+                hand("AH 2H 3H 4H 5H"),
+                hand("AS 2S 3S 4S 5S"),
+                hand("6C 7C 8C 9C TC"),
+                hand("6D 7D 8D 9D TD"),
+            ];
+            let dealer = 5;
+
+            // Each player discards their last card, making a crib of six
+            let mut crib = Vec::new();
+            let mut kept = Vec::new();
+            for (seat, mut cards) in dealt.into_iter().enumerate() {
+                let discards = discards_required(&config, seat as u8, dealer);
+                crib.extend(cards.split_off(cards.len() - discards));
+                kept.push(cards);
+            }
+            assert_eq!(crib.len(), 6);
+            assert!(kept
+                .iter()
+                .all(|cards| cards.len() == kept_hand_size(&config)));
+
+            let mut play = super::super::play::PlayState::new(kept, dealer, config.five_card);
+            assert_eq!(play.turn, 0);
+            let mut played = 0;
+            let mut turn = Some(play.turn);
+            let mut points = vec![0 as u8; config.num_sides() as usize];
+            while let Some(seat) = turn {
+                let index = play.playable(seat)[0];
+                let (_, card_points) = play.play(seat, index).unwrap();
+                play.peg(seat, card_points);
+                points[config.side_of(seat) as usize] += card_points;
+                played += 1;
+
+                let (go, next) = play.next();
+                if let Some((seat, go_points)) = go {
+                    points[config.side_of(seat) as usize] += go_points;
+                }
+                turn = next;
+            }
+            assert_eq!(played, 24);
+
+            let mut pegged = vec![0 as u8; config.num_sides() as usize];
+            for (seat, seat_points) in play.pegged.iter().enumerate() {
+                pegged[config.side_of(seat as u8) as usize] += seat_points;
+            }
+            assert_eq!(pegged, points);
+        }
+    }
+
+    // Six players in two teams of three are dealt, discard, play, and show through the game loop
+    // until a team wins
+    #[test]
+    fn six_player_table_plays_to_a_team_win() {
+        let seats = run_table(table_config(6, "teams=2 points=61", "six_player"), None);
+
+        for received in &seats {
+            // Every seat discards one card of the five it is dealt each hand
+            let hands = received
+                .iter()
+                .filter(|message| {
+                    matches!(message, super::super::messages::GameToClient::DealtHand(cards)
+                        if cards.len() == 5)
+                })
+                .count();
+            let discards = received
+                .iter()
+                .filter(|message| {
+                    matches!(
+                        message,
+                        super::super::messages::GameToClient::WaitDiscardOne
+                    )
+                })
+                .count();
+            assert!(hands > 0);
+            assert_eq!(hands, discards);
+
+            // Every hand is played, the crib of six is shown, and a team wins
+            assert!(received.iter().any(|message| matches!(
+                message,
+                super::super::messages::GameToClient::CardPlayed { .. }
+            )));
+            assert!(received.iter().all(|message| match message {
+                super::super::messages::GameToClient::ShowCrib { crib, .. } => crib.len() == 6,
+                _ => true,
+            }));
+            assert!(received.iter().any(|message| matches!(
+                message,
+                super::super::messages::GameToClient::GameWinner(0)
+                    | super::super::messages::GameToClient::GameWinner(1)
+            )));
+            assert!(matches!(
+                received.last(),
+                Some(super::super::messages::GameToClient::Disconnect)
+            ));
+        }
+    }
+
+    // The dealer of five players is dealt four cards and never asked to discard, so the crib is
+    // made of one card from each of the other four players
+    #[test]
+    fn five_player_table_deals_the_dealer_four_cards() {
+        let seats = run_table(table_config(5, "", "five_player"), None);
+
+        let mut cribs = 0;
+        for received in &seats {
+            // The dealer is asked to deal and the hand is theirs until every discard is placed
+            let mut is_dealer = false;
+            for message in received {
+                match message {
+                    super::super::messages::GameToClient::WaitDeal => is_dealer = true,
+                    super::super::messages::GameToClient::AllDiscards => is_dealer = false,
+                    super::super::messages::GameToClient::DealtHand(cards) => {
+                        assert_eq!(cards.len(), if is_dealer { 4 } else { 5 });
+                    }
+                    super::super::messages::GameToClient::WaitDiscardOne => assert!(!is_dealer),
+                    super::super::messages::GameToClient::ShowCrib { crib, .. } => {
+                        assert_eq!(crib.len(), 4);
+                        cribs += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert!(cribs > 0);
+    }
+
+    // When a player other than the dealer wins the crib they deal in effect; the play and the show
+    // start from the player after them, and they show their hand last and then their crib
+    #[test]
+    fn auction_winner_plays_and_shows_as_the_dealer() {
+        let seats = run_table(
+            table_config(3, "auction=true points=61", "auction"),
+            Some(1),
+        );
+
+        // Every seat is sent the same announcements, so the hands are followed from the first
+        let mut winner = 0;
+        let mut dealer = 0;
+        let mut is_first_card = false;
+        let mut shows = Vec::new();
+        let mut hands_checked = 0;
+        for message in &seats[0] {
+            match message {
+                super::super::messages::GameToClient::AuctionResult { player, .. } => {
+                    winner = *player;
+                    shows.clear();
+                }
+                super::super::messages::GameToClient::CutStarter(cutter, _) => {
+                    dealer = (cutter + 2) % 3;
+                    is_first_card = true;
+                }
+                super::super::messages::GameToClient::CardPlayed { player, .. }
+                    if is_first_card =>
+                {
+                    assert_eq!(*player, (winner + 1) % 3);
+                    is_first_card = false;
+                }
+                super::super::messages::GameToClient::ShowHand { player, .. } => {
+                    shows.push(*player);
+                }
+                super::super::messages::GameToClient::ShowCrib { player, .. } => {
+                    assert_eq!(shows, vec![(winner + 1) % 3, (winner + 2) % 3, winner]);
+                    assert_eq!(*player, winner);
+                    if winner != dealer {
+                        hands_checked += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert!(hands_checked > 0);
+    }
+}
@@ -0,0 +1,202 @@
+extern crate cribbage;
+use serde::{Deserialize, Serialize};
+
+// A way for a player to discard from their dealt hand and the points it is expected to give
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DiscardHint {
+    // The indices in the hand of the cards to discard
+    pub discards: Vec<u8>,
+
+    // The average points of the cards kept over every starter that could be cut, and the average
+    // points of the crib the discards are put in over deals of the cards the player can not see as
+    // the rest of the crib and the starter
+    pub expected_hand: f64,
+    pub expected_crib: f64,
+}
+
+// The number of deals of the unseen cards the crib is averaged over; the same deals are used for
+// every way to discard so that they are ranked against each other fairly
+const CRIB_DEALS: usize = 1000;
+
+// The seed the deals of the unseen cards are shuffled from, so that a hand is always given the
+// same hints
+const CRIB_SEED: u64 = 0x5EED_C41B;
+
+// The points the cards count for with the starter
+fn points(cards: &Vec<cribbage::deck::Card>, starter: &cribbage::deck::Card, is_crib: bool) -> f64 {
+    cribbage::score::score_hand(cards, starter, is_crib)
+        .iter()
+        .map(|event| event.point_value() as f64)
+        .sum()
+}
+
+// Every way to choose the given number of card indices, one or two, from a hand of the given size
+fn discard_choices(hand_size: u8, discard_count: usize) -> Vec<Vec<u8>> {
+    let mut choices = Vec::new();
+    for first in 0..hand_size {
+        if discard_count == 1 {
+            choices.push(vec![first]);
+        } else {
+            for second in first + 1..hand_size {
+                choices.push(vec![first, second]);
+            }
+        }
+    }
+    choices
+}
+
+// Deals of the cards the player can not see as the given number of cards the rest of the crib is
+// made of, the kitty and the discards of the other players, and the starter
+fn crib_deals(
+    hand: &Vec<cribbage::deck::Card>,
+    others: usize,
+) -> Vec<(Vec<cribbage::deck::Card>, cribbage::deck::Card)> {
+    let mut deck_shuffler =
+        super::deck::DeckShuffler::new(super::deck::DeckSetup::Seeded(CRIB_SEED));
+    (0..CRIB_DEALS)
+        .map(|_| {
+            let mut unseen: Vec<cribbage::deck::Card> = deck_shuffler
+                .next_deck()
+                .into_iter()
+                .filter(|card| !hand.contains(card))
+                .collect();
+            let starter = unseen.remove(others);
+            unseen.truncate(others);
+            (unseen, starter)
+        })
+        .collect()
+}
+
+// Works out every way to discard the given number of cards from the hand to a crib of the given
+// size and ranks them from best to worst; the expected points of the crib count for the player
+// when their side owns the crib and against them otherwise
+pub fn discard_hints(
+    hand: &Vec<cribbage::deck::Card>,
+    discard_count: usize,
+    crib_size: usize,
+    owns_crib: bool,
+) -> Vec<DiscardHint> {
+    // Any card the player can not see could be the starter
+    let starters: Vec<cribbage::deck::Card> = super::deck::full_deck()
+        .into_iter()
+        .filter(|card| !hand.contains(card))
+        .collect();
+    let deals = crib_deals(hand, crib_size.saturating_sub(discard_count));
+
+    let mut hints: Vec<DiscardHint> = discard_choices(hand.len() as u8, discard_count)
+        .into_iter()
+        .map(|discards| {
+            let (thrown, kept): (Vec<(usize, &cribbage::deck::Card)>, _) = hand
+                .iter()
+                .enumerate()
+                .partition(|(index, _)| discards.contains(&(*index as u8)));
+            let thrown: Vec<cribbage::deck::Card> =
+                thrown.into_iter().map(|(_, card)| card.clone()).collect();
+            let kept: Vec<cribbage::deck::Card> =
+                kept.into_iter().map(|(_, card)| card.clone()).collect();
+
+            let expected_hand = starters
+                .iter()
+                .map(|starter| points(&kept, starter, false))
+                .sum::<f64>()
+                / starters.len() as f64;
+            let expected_crib = deals
+                .iter()
+                .map(|(others, starter)| {
+                    let mut crib = thrown.clone();
+                    crib.extend(others.iter().cloned());
+                    points(&crib, starter, true)
+                })
+                .sum::<f64>()
+                / deals.len() as f64;
+
+            DiscardHint {
+                expected_hand: expected_hand,
+                expected_crib: expected_crib,
+                discards: discards,
+            }
+        })
+        .collect();
+
+    let value = |hint: &DiscardHint| {
+        if owns_crib {
+            hint.expected_hand + hint.expected_crib
+        } else {
+            hint.expected_hand - hint.expected_crib
+        }
+    };
+    hints.sort_by(|a, b| value(b).partial_cmp(&value(a)).unwrap());
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(cards: &str) -> Vec<cribbage::deck::Card> {
+        cards
+            .split_whitespace()
+            .map(|card| super::super::deck::parse_card(card).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn every_discard_is_ranked_from_best_to_worst() {
+        let hand = cards("5C 5D JH QS 2C 9D");
+        for owns_crib in vec![true, false] {
+            let hints = discard_hints(&hand, 2, 4, owns_crib);
+            assert_eq!(hints.len(), 15);
+            let sign = if owns_crib { 1.0 } else { -1.0 };
+            let values: Vec<f64> = hints
+                .iter()
+                .map(|hint| hint.expected_hand + sign * hint.expected_crib)
+                .collect();
+            assert!(values.windows(2).all(|pair| pair[0] >= pair[1]));
+        }
+    }
+
+    #[test]
+    fn crib_counts_more_than_the_discards_alone() {
+        let hand = cards("5C 5D JH QS 2C 9D");
+        let hints = discard_hints(&hand, 2, 4, true);
+
+        // The pair of fives is worth two with any starter and four more with any ten-card, and
+        // the two cards the other player discards only add to that
+        let fives = hints
+            .iter()
+            .find(|hint| hint.discards == vec![0, 1])
+            .unwrap();
+        let starters: Vec<cribbage::deck::Card> = super::super::deck::full_deck()
+            .into_iter()
+            .filter(|card| !hand.contains(card))
+            .collect();
+        let alone = starters
+            .iter()
+            .map(|starter| points(&cards("5C 5D"), starter, true))
+            .sum::<f64>()
+            / starters.len() as f64;
+        assert!(alone > 2.0);
+        assert!(fives.expected_crib > alone);
+
+        // Keeping the fives with the jack and queen is the best hand to keep
+        let best_kept = hints
+            .iter()
+            .max_by(|a, b| a.expected_hand.partial_cmp(&b.expected_hand).unwrap())
+            .unwrap();
+        assert_eq!(best_kept.discards, vec![4, 5]);
+    }
+
+    #[test]
+    fn same_hand_is_given_the_same_hints() {
+        let hand = cards("AC 4D 7H TS KC 3D");
+        let first = discard_hints(&hand, 2, 4, false);
+        let second = discard_hints(&hand, 2, 4, false);
+        let crib = |hints: &Vec<DiscardHint>| -> Vec<(Vec<u8>, f64)> {
+            hints
+                .iter()
+                .map(|hint| (hint.discards.clone(), hint.expected_crib))
+                .collect()
+        };
+        assert_eq!(crib(&first), crib(&second));
+    }
+}
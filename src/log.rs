@@ -0,0 +1,389 @@
+extern crate cribbage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+
+// A copy of each GameEvent processed by the game object that can be written to and read from the
+// game log; the cribbage::GameEvent itself can not be serialized
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LoggedEvent {
+    GameSetup {
+        names: Vec<String>,
+        manual: bool,
+        underscoring: bool,
+        muggins: bool,
+        overscoring: bool,
+    },
+    Confirmation,
+    DiscardSelection(Vec<Vec<cribbage::deck::Card>>),
+}
+
+impl LoggedEvent {
+    // Creates the GameEvent to be given to process_event
+    pub fn to_game_event(&self) -> cribbage::GameEvent {
+        match self {
+            LoggedEvent::GameSetup {
+                names,
+                manual,
+                underscoring,
+                muggins,
+                overscoring,
+            } => cribbage::GameEvent::GameSetup {
+                input_player_names: names.clone(),
+                input_manual: *manual,
+                input_underscoring: *underscoring,
+                input_muggins: *muggins,
+                input_overscoring: *overscoring,
+            },
+            LoggedEvent::Confirmation => cribbage::GameEvent::Confirmation,
+            LoggedEvent::DiscardSelection(discards) => {
+                cribbage::GameEvent::DiscardSelection(discards.clone())
+            }
+        }
+    }
+}
+
+// A single record in the game log
+#[derive(Debug, Serialize, Deserialize)]
+pub enum LogEntry {
+    // How the decks of the table are ordered; the first entry of every log
+    DeckSetup(super::deck::DeckSetup),
+
+    // That the game object's deck was replaced with the given deck before the next event
+    Deck(Vec<cribbage::deck::Card>),
+
+    // That the event was given to the game object's process_event
+    Event(LoggedEvent),
+
+    // That the message was sent to every client at the table
+    Broadcast(super::messages::GameToClient),
+
+    // That a game of the match ended and the game object was replaced with a new one; the
+    // GameSetup event of the next game follows
+    NewGame,
+
+    // That the game object was given the seat to first deal instead of cutting for it
+    FirstDealer(u8),
+
+    // That every hand was cut down to the given number of cards after the deal, the extra cards
+    // going back to the deck, as in five-card cribbage
+    TrimHands(usize),
+
+    // That the table rules gave the player with the given index points outside of the scoring of
+    // the game object, such as the three points for last given to the non-dealer in five-card
+    // cribbage
+    Points { player: u8, points: u8 },
+
+    // That the table rules took points from the player with the given index, such as the bid of
+    // the winner of the crib in Auction cribbage
+    Deduction { player: u8, points: u8 },
+
+    // That the player with the given index won the crib of the hand in Auction cribbage and deals
+    // in effect for it; the table keeps the dealer in effect itself, so the game object is left as
+    // it is
+    CribOwner(u8),
+
+    // That the hand was played and shown by the table and the game object was made ready to deal
+    // the next hand with the given dealer
+    NextHand(u8),
+
+    // That the table was restored from a snapshot taken after the given number of entries; any
+    // entries written between the snapshot and the table being restored are discarded
+    Resumed { entries: usize },
+}
+
+// An append-only log of everything that happens at a table; each entry is written as its length
+// as a little endian u32 followed by the bincode serialized LogEntry
+pub struct GameLog {
+    file: fs::File,
+
+    // The number of entries in effect, ie. not discarded by a Resumed entry; saved in snapshots so
+    // that the game object can be rebuilt to the point the snapshot was taken
+    pub entries: usize,
+
+    // Whether or not a write has failed; no entry is appended after a failed write so that the log
+    // never has an entry missing from the middle of it
+    failed: bool,
+}
+
+impl GameLog {
+    // Opens the log at the given path, creating it if it does not exist and only ever appending
+    // to it if it does; a truncated final entry, as left by a crash while writing, is cut off so
+    // that new entries are not appended to it
+    pub fn open(path: &str) -> Result<GameLog, String> {
+        let file = match fs::OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => file,
+            Err(e) => return Err(format!("Could not open log {}; {}", path, e)),
+        };
+
+        let (entries, length) = read_entries(path)?;
+        if let Err(e) = file.set_len(length) {
+            return Err(format!("Could not cut the end off log {}; {}", path, e));
+        }
+
+        Ok(GameLog {
+            file: file,
+            entries: entries.len(),
+            failed: false,
+        })
+    }
+
+    // Writes the entry to the end of the log and flushes it so that it survives the process dying;
+    // returns an error if it could not be written, after which every later entry is refused
+    pub fn append(&mut self, entry: &LogEntry) -> Result<(), String> {
+        if self.failed {
+            return Err("The log is not written to after a failed write".to_string());
+        }
+
+        let written = match bincode::serialize(entry) {
+            Ok(bytes) => self
+                .file
+                .write_all(&(bytes.len() as u32).to_le_bytes())
+                .and_then(|_| self.file.write_all(&bytes))
+                .and_then(|_| self.file.flush())
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = written {
+            self.failed = true;
+            return Err(format!("Could not write to the log; {}", e));
+        }
+
+        match entry {
+            LogEntry::Resumed { entries } => self.entries = *entries,
+            _ => self.entries += 1,
+        }
+        Ok(())
+    }
+
+    // Whether or not a write has failed, leaving the log unable to restore or replay the table
+    pub fn has_failed(&self) -> bool {
+        self.failed
+    }
+}
+
+// The helpers below change the game object directly for rules it does not handle itself. The table
+// relies on the game object keeping no other record of the scores, hands, dealer, and state, so
+// that a change made here is seen by every later event; each change is logged and made again in
+// the same order by rebuild and replay
+
+// Skips the initial cut of a freshly set up game object by making the given seat the dealer. The
+// game object keeps no record of the cut beyond index_dealer, so moving it straight to Deal leaves
+// it as it would be had the cut chosen the seat
+pub fn set_first_dealer(game_object: &mut cribbage::Game, dealer: u8) {
+    game_object.index_dealer = dealer;
+    game_object.state = cribbage::GameState::Deal;
+}
+
+// Cuts every hand down to the given number of cards, returning the extra cards to the deck
+pub fn trim_hands(game_object: &mut cribbage::Game, size: usize) {
+    for index in 0..game_object.players.len() {
+        if game_object.players[index].hand.len() > size {
+            let mut extra = game_object.players[index].hand.split_off(size);
+            game_object.deck.append(&mut extra);
+        }
+    }
+}
+
+// Gives points to a player outside of the scoring of the game object, such as the points for last
+// in five-card cribbage and every point of the play and the show. The points are added to the
+// score the game object keeps, which the table reads to decide the winner, so they count from the
+// next event on
+pub fn give_points(game_object: &mut cribbage::Game, player: u8, points: u8) {
+    let player = &mut game_object.players[player as usize];
+    player.score = player.score.saturating_add(points);
+}
+
+// Clears the hands and crib of the hand the table has just played and shown, and gives the deal to
+// the given seat; the deck is replaced before every deal so it is left as it is
+pub fn next_hand(game_object: &mut cribbage::Game, dealer: u8) {
+    for player in &mut game_object.players {
+        player.hand.clear();
+    }
+    game_object.crib.clear();
+    game_object.index_dealer = dealer;
+    game_object.state = cribbage::GameState::Deal;
+}
+
+// Takes points from a player outside of the scoring of the game object
+pub fn take_points(game_object: &mut cribbage::Game, player: u8, points: u8) {
+    let player = &mut game_object.players[player as usize];
+    player.score = player.score.saturating_sub(points);
+}
+
+// Reads every entry in effect from the log at the given path along with the length in bytes of
+// the complete entries; a truncated final entry is ignored
+fn read_entries(path: &str) -> Result<(Vec<LogEntry>, u64), String> {
+    let mut bytes = Vec::new();
+    match fs::File::open(path) {
+        Ok(mut file) => {
+            if let Err(e) = file.read_to_end(&mut bytes) {
+                return Err(format!("Could not read log {}; {}", path, e));
+            }
+        }
+        Err(e) => return Err(format!("Could not open log {}; {}", path, e)),
+    }
+
+    let mut entries = Vec::new();
+    let mut position = 0;
+    while position + 4 <= bytes.len() {
+        let mut length = [0 as u8; 4];
+        length.copy_from_slice(&bytes[position..position + 4]);
+        let length = u32::from_le_bytes(length) as usize;
+
+        if position + 4 + length > bytes.len() {
+            println!("Ignoring truncated entry at the end of {}", path);
+            break;
+        }
+
+        match bincode::deserialize(&bytes[position + 4..position + 4 + length]) {
+            Ok(LogEntry::Resumed { entries: kept }) => entries.truncate(kept),
+            Ok(entry) => entries.push(entry),
+            Err(e) => return Err(format!("Invalid entry in log {}; {}", path, e)),
+        }
+        position += 4 + length;
+    }
+
+    Ok((entries, position as u64))
+}
+
+// Reads every entry in effect from the log at the given path
+pub fn read_log(path: &str) -> Result<Vec<LogEntry>, String> {
+    Ok(read_entries(path)?.0)
+}
+
+// Rebuilds the game object from the first given number of entries in the log without printing
+// anything; used to restore a table from a snapshot
+pub fn rebuild(path: &str, entries: usize) -> Result<cribbage::Game, String> {
+    let mut game_object = cribbage::Game::new();
+
+    for entry in read_log(path)?.into_iter().take(entries) {
+        match entry {
+            LogEntry::Deck(deck) => game_object.deck = deck,
+            LogEntry::Event(event) => {
+                if let Err(e) = game_object.process_event(event.to_game_event()) {
+                    return Err(format!("Event {:?} failed; {}", event, e));
+                }
+            }
+            LogEntry::NewGame => game_object = cribbage::Game::new(),
+            LogEntry::FirstDealer(dealer) => set_first_dealer(&mut game_object, dealer),
+            LogEntry::TrimHands(size) => trim_hands(&mut game_object, size),
+            LogEntry::Points { player, points } => give_points(&mut game_object, player, points),
+            LogEntry::Deduction { player, points } => take_points(&mut game_object, player, points),
+            LogEntry::NextHand(dealer) => next_hand(&mut game_object, dealer),
+            _ => {}
+        }
+    }
+
+    Ok(game_object)
+}
+
+// Rebuilds the game object from the log one event at a time, printing every step and checking
+// every broadcast score update against the scores held by the rebuilt game object
+pub fn replay(path: &str) -> Result<cribbage::Game, String> {
+    let mut game_object = cribbage::Game::new();
+
+    // The player indices on each team once a Teams broadcast is read; score updates are then
+    // checked against the pooled scores of each team
+    let mut teams: Option<Vec<Vec<u8>>> = None;
+
+    for (number, entry) in read_log(path)?.into_iter().enumerate() {
+        match entry {
+            LogEntry::DeckSetup(setup) => println!("{}: Deck setup {:?}", number, setup),
+
+            LogEntry::Deck(deck) => {
+                println!("{}: Deck replaced", number);
+                game_object.deck = deck;
+            }
+
+            LogEntry::Event(event) => {
+                if let Err(e) = game_object.process_event(event.to_game_event()) {
+                    return Err(format!("{}: Event {:?} failed; {}", number, event, e));
+                }
+                println!(
+                    "{}: Processed {:?}, state is now {:?}",
+                    number, event, game_object.state
+                );
+            }
+
+            LogEntry::NewGame => {
+                println!("{}: New game", number);
+                game_object = cribbage::Game::new();
+            }
+
+            LogEntry::FirstDealer(dealer) => {
+                println!("{}: First dealer is player {}", number, dealer);
+                set_first_dealer(&mut game_object, dealer);
+            }
+
+            LogEntry::TrimHands(size) => {
+                println!("{}: Hands cut down to {} cards", number, size);
+                trim_hands(&mut game_object, size);
+            }
+
+            LogEntry::Points { player, points } => {
+                println!("{}: Player {} given {} points", number, player, points);
+                give_points(&mut game_object, player, points);
+            }
+
+            LogEntry::Deduction { player, points } => {
+                println!("{}: Player {} lost {} points", number, player, points);
+                take_points(&mut game_object, player, points);
+            }
+
+            LogEntry::CribOwner(player) => {
+                println!("{}: Player {} owns the crib", number, player);
+            }
+
+            LogEntry::NextHand(dealer) => {
+                println!("{}: Next hand dealt by player {}", number, dealer);
+                next_hand(&mut game_object, dealer);
+            }
+
+            LogEntry::Broadcast(super::messages::GameToClient::Teams(roster)) => {
+                println!("{}: Broadcast teams {:?}", number, roster);
+                teams = Some(roster);
+            }
+
+            LogEntry::Broadcast(super::messages::GameToClient::ScoreUpdate(scores)) => {
+                println!("{}: Broadcast scores {:?}", number, scores);
+                for (index, score) in &scores {
+                    match &teams {
+                        Some(teams) => match teams.get(*index as usize) {
+                            Some(team) => {
+                                let team_score = team
+                                    .iter()
+                                    .filter_map(|seat| game_object.players.get(*seat as usize))
+                                    .fold(0 as u8, |total, player| {
+                                        total.saturating_add(player.score)
+                                    });
+                                if team_score != *score {
+                                    println!(
+                                        "{}: Score mismatch for team {}; broadcast {} but replay has {}",
+                                        number, index, score, team_score
+                                    );
+                                }
+                            }
+                            None => println!("{}: Score for unknown team {}", number, index),
+                        },
+                        None => match game_object.players.get(*index as usize) {
+                            Some(player) if player.score == *score => {}
+                            Some(player) => println!(
+                                "{}: Score mismatch for {}; broadcast {} but replay has {}",
+                                number, player.username, score, player.score
+                            ),
+                            None => println!("{}: Score for unknown player {}", number, index),
+                        },
+                    }
+                }
+            }
+
+            LogEntry::Broadcast(message) => println!("{}: Broadcast {:?}", number, message),
+
+            // Resumed entries are applied when reading the log
+            LogEntry::Resumed { .. } => {}
+        }
+    }
+
+    Ok(game_object)
+}
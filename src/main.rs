@@ -1,35 +1,167 @@
 extern crate cribbage;
 extern crate serde;
+mod accounts;
 mod client;
+mod config;
+mod connection;
+mod deck;
 mod game;
+mod hints;
+mod log;
+mod match_play;
 mod messages;
+mod play;
+mod practice;
+mod ratings;
+mod snapshot;
+mod stats;
+mod tournament;
 use std::env;
 use std::io;
 use std::net;
-use std::str;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 
+// Spawns a client handler thread for a new connection. The connection is set up on the new thread
+// so that a slow handshake does not hold up the main thread, and the client is only handed to the
+// game model thread once the connection is ready
+fn spawn_client_handler(
+    socket: net::TcpStream,
+    is_websocket: bool,
+    tls_acceptor: &Option<native_tls::TlsAcceptor>,
+    queries: &client::Queries,
+    main_to_game_handler_transmitter: &mpsc::Sender<messages::MainToGame>,
+) {
+    let tls_acceptor = tls_acceptor.clone();
+    let queries = queries.clone();
+    let main_to_game_handler_transmitter = main_to_game_handler_transmitter.clone();
+
+    thread::spawn(move || {
+        // Sockets accepted from a non-blocking listener may be non-blocking themselves
+        socket.set_nonblocking(false).unwrap();
+
+        let stream = match connection::Stream::accept(socket, &tls_acceptor) {
+            Ok(stream) => stream,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+
+        let connection = if is_websocket {
+            match connection::Connection::websocket(stream) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    println!("WebSocket handshake failed; {}", e);
+                    return;
+                }
+            }
+        } else {
+            match connection::Connection::negotiate(stream) {
+                Ok(connection) => connection,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            }
+        };
+
+        // Creates the transmitters and receivers used by the game model to communicate with the
+        // client handler thread
+        let (client_handler_to_game_handler_transmitter, client_handler_to_game_handler_receiver) =
+            mpsc::channel();
+        let (game_handler_to_client_handler_transmitter, game_handler_to_client_handler_receiver) =
+            mpsc::channel();
+
+        // Send the other transmitter and receiver to the game model thread
+        main_to_game_handler_transmitter
+            .send(messages::MainToGame::NewClient {
+                transmitter: game_handler_to_client_handler_transmitter,
+                receiver: client_handler_to_game_handler_receiver,
+            })
+            .unwrap();
+
+        client::handle_client(
+            connection,
+            client_handler_to_game_handler_transmitter,
+            game_handler_to_client_handler_receiver,
+            queries,
+        );
+    });
+}
+
 fn main() {
-    // Command line arguments are a port number, the number of players to wait for, and whether or
-    // not manual scoring, underscoring, muggins, and overscoring are enabled in that order (sample
-    // input is "executable_name 1025 2 false false false false"); lobby will launch a server
-    // instance on a free port and direct players to it
+    // Command line arguments are those described in TableConfig::from_args (sample input is
+    // "executable_name 1025 2 false false false false"); lobby will launch a server instance on a
+    // free port and direct players to it
 
     let args: Vec<String> = env::args().collect();
 
-    // Adds the port to listen to on to the local IP
-    let mut address = "127.0.0.1:".to_string();
-    address.push_str(&args[1]);
+    // Alternatively the arguments "replay path" rebuild a game from its log to check the scores
+    // that were given to the players (sample input is "executable_name replay game_1025.log")
+    if args[1] == "replay" {
+        match log::replay(&args[2]) {
+            Ok(game) => println!("Replay finished in state {:?}", game.state),
+            Err(e) => println!("Replay failed; {}", e),
+        }
+        return;
+    }
 
-    // Parses the number of players to wait for
-    let desired_num_players: u8 = args[2].trim().parse().unwrap();
+    // Or the arguments "tournament create path format entrant..." create a single, double, or
+    // roundrobin tournament between the given accounts, "tournament run path port options..."
+    // opens its tables from the given port until it is finished, and "tournament standings path"
+    // prints its standings (sample input is "executable_name tournament create cup.json single
+    // alice bob carol" then "executable_name tournament run cup.json 1025 false false false false")
+    if args[1] == "tournament" {
+        let result = match args[2].as_str() {
+            "create" => tournament::Format::parse(&args[4]).and_then(|format| {
+                let tournament = tournament::Tournament::new(format, args[5..].to_vec())?;
+                tournament.save(&args[3])?;
+                tournament::print_standings(&tournament);
+                Ok(())
+            }),
+            "run" => tournament::run(&args[3], args[4].trim().parse().unwrap(), &args[5..]),
+            "standings" => tournament::Tournament::load(&args[3])
+                .map(|tournament| tournament::print_standings(&tournament)),
+            command => Err(format!("Unknown tournament command {}", command)),
+        };
+        if let Err(e) = result {
+            println!("Tournament failed; {}", e);
+        }
+        return;
+    }
 
-    // Parses the boolean options given in the command line arguments
-    let man_score: bool = str::FromStr::from_str(&args[3]).unwrap();
-    let underpegging: bool = str::FromStr::from_str(&args[4]).unwrap();
-    let muggins: bool = str::FromStr::from_str(&args[5]).unwrap();
-    let overpegging: bool = str::FromStr::from_str(&args[6]).unwrap();
+    // Or the arguments "resume path" restore a table from its snapshot after the server died and
+    // wait for the players to reconnect to their seats (sample input is
+    // "executable_name resume game_1025.snapshot")
+    let (config, game, snapshot) = if args[1] == "resume" {
+        let restored = snapshot::TableSnapshot::load(&args[2]).and_then(|snapshot| {
+            let game = log::rebuild(&snapshot.config.log_path, snapshot.log_entries)?;
+            Ok((snapshot, game))
+        });
+        let (snapshot, game) = match restored {
+            Ok(restored) => restored,
+            Err(e) => {
+                println!("Could not restore table; {}", e);
+                return;
+            }
+        };
+        println!("Restored table from {}", args[2]);
+        (snapshot.config.clone(), game, Some(snapshot))
+    } else {
+        (
+            config::TableConfig::from_args(&args),
+            cribbage::Game::new(),
+            None,
+        )
+    };
+
+    println!("Using deck setup {:?}", config.deck_setup);
+    println!("Logging game to {}", config.log_path);
+
+    // Adds the port to listen to on to the local IP
+    let mut address = "127.0.0.1:".to_string();
+    address.push_str(&config.port);
 
     // The TCP listener to form connections
     let listener = net::TcpListener::bind(&address).unwrap();
@@ -37,27 +169,91 @@ fn main() {
         .set_nonblocking(true)
         .expect("Can not set listener non-blocking");
 
-    // The game object to be used on the server
-    let mut game = cribbage::Game::new();
+    let websocket_port = config.websocket_port.clone();
+
+    // Connections are encrypted with TLS when the table is given a certificate and key, both for
+    // TCP and WebSocket clients; otherwise they are plaintext
+    let tls_acceptor = match (&config.tls_certificate, &config.tls_key) {
+        (Some(certificate), Some(key)) => {
+            println!("Using TLS with certificate {}", certificate);
+            Some(connection::tls_acceptor(certificate, key).unwrap())
+        }
+        _ => None,
+    };
+
+    // The statistics of every account; recorded to by the game thread and read by the client
+    // handler threads to answer queries
+    let stats_store = match stats::StatsStore::open(&config.stats_path) {
+        Ok(stats_store) => Arc::new(Mutex::new(stats_store)),
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let game_stats_store = Arc::clone(&stats_store);
+
+    // The registered accounts, only opened when the table requires login; logins are checked by
+    // the client handler threads so that hashing a password does not hold up the table
+    let accounts = if config.require_login {
+        match accounts::AccountStore::open(&config.accounts_path) {
+            Ok(account_store) => Some(Arc::new(Mutex::new(account_store))),
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let queries = client::Queries {
+        stats_store: stats_store,
+        tournament_path: config.tournament_path.clone(),
+        accounts: accounts,
+    };
 
     let (game_handler_to_main_transmitter, game_handler_to_main_receiver) = mpsc::channel();
     let (main_to_game_handler_transmitter, main_to_game_handler_receiver) = mpsc::channel();
 
+    // A practice table is handled by the practice thread in place of the game thread
     thread::spawn(move || {
-        game::handle_game(
-            game,
-            desired_num_players,
-            man_score,
-            underpegging,
-            muggins,
-            overpegging,
-            main_to_game_handler_receiver,
-            game_handler_to_main_transmitter,
-        );
+        if config.practice.is_some() {
+            practice::handle_practice(
+                config,
+                main_to_game_handler_receiver,
+                game_handler_to_main_transmitter,
+            );
+        } else {
+            game::handle_game(
+                game,
+                config,
+                snapshot,
+                game_stats_store,
+                main_to_game_handler_receiver,
+                game_handler_to_main_transmitter,
+            );
+        }
     });
 
     println!("Waiting for connection on ip {}", address);
 
+    // The optional listener for browser clients connecting with WebSockets
+    let websocket_listener = match &websocket_port {
+        Some(port) => {
+            let websocket_address = format!("127.0.0.1:{}", port);
+            let websocket_listener = net::TcpListener::bind(&websocket_address).unwrap();
+            websocket_listener
+                .set_nonblocking(true)
+                .expect("Can not set listener non-blocking");
+            println!(
+                "Waiting for WebSocket connection on ip {}",
+                websocket_address
+            );
+            Some(websocket_listener)
+        }
+        None => None,
+    };
+
     let mut game_over = false;
     while !game_over {
         // Continuously poll for an end server event
@@ -65,38 +261,16 @@ fn main() {
             Ok(messages::GameToMain::EndServer) => true,
             _ => false,
         };
-        // And poll for a new connection to the listener
+        // And poll for a new connection to the listeners
         match listener.accept() {
             Ok((socket, address)) => {
-                // Creates the transmitters and receivers used by the game model to communicate
-                // with each client handler thread
-                let (
-                    client_handler_to_game_handler_transmitter,
-                    client_handler_to_game_handler_receiver,
-                ) = mpsc::channel();
-                let (
-                    game_handler_to_client_handler_transmitter,
-                    game_handler_to_client_handler_receiver,
-                ) = mpsc::channel();
-
-                // Spawns the client handler thread and gives ownership of the proper transmitter
-                // and receiver
-                thread::spawn(move || {
-                    client::handle_client(
-                        socket,
-                        client_handler_to_game_handler_transmitter,
-                        game_handler_to_client_handler_receiver,
-                    );
-                });
-
-                // Send the other transmitter and receiver to the game model thread
-                main_to_game_handler_transmitter
-                    .send(messages::MainToGame::NewClient {
-                        transmitter: game_handler_to_client_handler_transmitter,
-                        receiver: client_handler_to_game_handler_receiver,
-                    })
-                    .unwrap();
-
+                spawn_client_handler(
+                    socket,
+                    false,
+                    &tls_acceptor,
+                    &queries,
+                    &main_to_game_handler_transmitter,
+                );
                 println!("Connected to client on {}", address);
             }
             Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
@@ -104,6 +278,24 @@ fn main() {
                 println!("{}", e);
             }
         };
+        if let Some(websocket_listener) = &websocket_listener {
+            match websocket_listener.accept() {
+                Ok((socket, address)) => {
+                    spawn_client_handler(
+                        socket,
+                        true,
+                        &tls_acceptor,
+                        &queries,
+                        &main_to_game_handler_transmitter,
+                    );
+                    println!("Connected to WebSocket client on {}", address);
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    println!("{}", e);
+                }
+            };
+        }
     }
 
     println!("Exiting server");
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+// The progress of a match of consecutive games played by the same seats at a table; a table
+// playing a single game is a match of one game. Games are won by sides, which are teams when
+// players are partnered and otherwise single players
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MatchState {
+    // The number of games the match is the best of and whether or not a skunk counts as two game
+    // wins for the winner
+    pub best_of: u8,
+    pub skunk_counts_double: bool,
+
+    // The game wins of each side in order by index
+    pub wins: Vec<u8>,
+
+    // The number of seats the deal rotates around
+    pub seats: u8,
+
+    // The number of games finished so far
+    pub games_played: u8,
+
+    // The first dealer of the game in progress; the deal alternates between games so the next
+    // game is first dealt by the seat after this one
+    pub first_dealer: u8,
+}
+
+impl MatchState {
+    pub fn new(config: &super::config::TableConfig) -> MatchState {
+        MatchState {
+            best_of: config.match_games,
+            skunk_counts_double: config.skunk_counts_double,
+            wins: vec![0; config.num_sides() as usize],
+            seats: config.num_players,
+            games_played: 0,
+            first_dealer: 0,
+        }
+    }
+
+    // Whether or not the table plays more than a single game
+    pub fn is_match(&self) -> bool {
+        self.best_of > 1
+    }
+
+    // The number of game wins needed to win the match; a majority of the games in the match. With
+    // more than two players nobody may have a majority after best_of games, in which case the
+    // match continues until somebody does
+    fn wins_needed(&self) -> u8 {
+        self.best_of / 2 + 1
+    }
+
+    // Records the winning side of a finished game, and whether or not they skunked a loser, and
+    // returns the index of the winning side if the match has been decided
+    pub fn record_game(&mut self, winner: u8, skunked: bool) -> Option<u8> {
+        self.games_played += 1;
+
+        self.wins[winner as usize] += if skunked && self.skunk_counts_double {
+            2
+        } else {
+            1
+        };
+
+        if self.wins[winner as usize] >= self.wins_needed() {
+            Some(winner)
+        } else {
+            None
+        }
+    }
+
+    // Advances the deal to the seat after the first dealer of the last game and returns it
+    pub fn next_first_dealer(&mut self) -> u8 {
+        self.first_dealer = (self.first_dealer + 1) % self.seats;
+        self.first_dealer
+    }
+
+    // Pairs each side index with its game wins
+    pub fn score(&self) -> Vec<(u8, u8)> {
+        self.wins
+            .iter()
+            .enumerate()
+            .map(|(index, wins)| (index as u8, *wins))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn match_state(optional: &str) -> MatchState {
+        MatchState::new(&super::super::config::TableConfig::from_test_args(
+            2, optional,
+        ))
+    }
+
+    #[test]
+    fn single_game_is_decided_by_its_winner() {
+        let mut state = match_state("");
+        assert!(!state.is_match());
+        assert_eq!(state.record_game(1, false), Some(1));
+    }
+
+    #[test]
+    fn best_of_three_needs_two_wins() {
+        let mut state = match_state("games=3");
+        assert!(state.is_match());
+        assert_eq!(state.record_game(0, false), None);
+        assert_eq!(state.record_game(1, false), None);
+        assert_eq!(state.record_game(1, false), Some(1));
+        assert_eq!(state.games_played, 3);
+        assert_eq!(state.score(), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn best_of_an_even_number_needs_a_majority() {
+        let mut state = match_state("games=4");
+        assert_eq!(state.record_game(0, false), None);
+        assert_eq!(state.record_game(0, false), None);
+        assert_eq!(state.record_game(1, false), None);
+        assert_eq!(state.record_game(0, false), Some(0));
+    }
+
+    #[test]
+    fn skunk_counts_double_only_when_enabled() {
+        let mut single = match_state("games=3");
+        assert_eq!(single.record_game(0, true), None);
+        assert_eq!(single.score(), vec![(0, 1), (1, 0)]);
+
+        let mut double = match_state("games=3 skunkdouble=true");
+        assert_eq!(double.record_game(0, true), Some(0));
+        assert_eq!(double.score(), vec![(0, 2), (1, 0)]);
+    }
+
+    #[test]
+    fn first_deal_alternates_between_games() {
+        let mut state = match_state("games=5");
+        state.first_dealer = 1;
+        assert_eq!(state.next_first_dealer(), 0);
+        assert_eq!(state.next_first_dealer(), 1);
+    }
+}
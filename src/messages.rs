@@ -5,7 +5,7 @@ use std::sync::mpsc;
 
 // Messages from the client handler threads to the game model thread; also the messages sent from
 // the client to the client handler thread over TCP
-#[derive(PartialEq, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
 pub enum ClientToGame {
     // A message to initiate communication between the client thread and the game thread and to
     // indicate that the client thread is ready to receive requests
@@ -18,26 +18,65 @@ pub enum ClientToGame {
     Denial,
 
     // The name the client wishes to be known by for the duration of the game
-    // TODO A way to determine this with user authentication for eventual lobby and account system
     Name(String),
 
+    // That the client wishes to log in to an existing account or register a new one and sit at the
+    // table under its username; sent when the table asks for a login. The client handler checks
+    // the password itself and tells the game model only the outcome
+    Login { username: String, password: String },
+    Register { username: String, password: String },
+
+    // That the client has logged in to or registered the account with the given username; sent
+    // only by the client handler, never accepted from the client itself
+    LoggedIn(String),
+
+    // That the client is the player who sat under the given name and is taking their seat back
+    // after the table was restored; the token is the one sent to them in a ReconnectToken when
+    // they sat. May be sent in place of a Name when the table is waiting for players to reconnect
+    Reconnect { name: String, token: String },
+
+    // The number of points the player bids for the crib in Auction cribbage
+    Bid(u8),
+
+    // A request for the ways to discard from the player's hand ranked from best to worst; may be
+    // sent while the client is asked for a discard and is answered with DiscardHints, or with a
+    // HintDenied when the table does not allow hints
+    RequestHint,
+
     // The index or indices given are to be discarded
     DiscardOne { index: u8 },
     DiscardTwo { index_one: u8, index_two: u8 },
 
-    // That a given index has been played; as a hand is four cards, an index of 0 to 3 will
-    // represent that card being played and a None will represent a go
+    // That the card at the given index of the player's hand, one of those listed in the WaitPlay,
+    // is played. The table says go for a player who can not play, so None is rejected
     PlayTurn(Option<u8>),
 
-    // That the included ScoreEvents have been given by the player for the most recent play
+    // That the included ScoreEvents have been given by the player for the most recent play; also
+    // the scores claimed for the hand at a counting practice table
     PlayScore(Vec<cribbage::score::ScoreEvent>),
 
+    // That the card the player was dealt at a Cribbage Squares practice table is to be placed in
+    // the square in the given row and column, each from 0 to 3
+    PlaceCard { row: u8, column: u8 },
+
+    // A query for the statistics of the account with the given username; may be sent whenever the
+    // client is asked for input and is answered with a Stats message
+    RequestStats { player: String },
+
+    // A query for the ratings of every rated account; may be sent whenever the client is asked for
+    // input and is answered with a Leaderboard message
+    RequestLeaderboard,
+
+    // A query for the standings of the tournament the table is part of; may be sent whenever the
+    // client is asked for input and is answered with a Standings message
+    RequestStandings,
+
     TransmissionReceived,
 }
 
 // Messages sent from the game model to the client handler threads which more directly interact
 // with the players; also the messages sent from the client handler to the client over TCP
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum GameToClient {
     // Message indicating that the maximum number of cliets that can play have already joined
     DeniedTableFull,
@@ -45,6 +84,18 @@ pub enum GameToClient {
     // That the game model is requesting the name that the client will go by
     WaitName,
 
+    // That the name given by the client was rejected for the included reason; followed by another
+    // WaitName
+    NameRejected(String),
+
+    // That the table requires an account and the game model is requesting the client log in or
+    // register
+    WaitLogin,
+
+    // That the login or registration given by the client was rejected for the included reason;
+    // followed by another WaitLogin
+    LoginRejected(String),
+
     // That a player has successfully joined the game
     PlayerJoinNotification {
         name: String,
@@ -52,17 +103,48 @@ pub enum GameToClient {
         of: u8,
     },
 
+    // The secret the player must send in a Reconnect to take their seat back if the table is
+    // restored after the server died; sent only to that player once they sit
+    ReconnectToken(String),
+
+    // That every seat at the table is filled; contains a vector of pairs of player indices and
+    // names. Messages about a player identify them by index, which this maps to the name to show
+    SeatRoster(Vec<(u8, String)>),
+
+    // That the players are partnered; contains the player indices on each team in order by team
+    // index, where in Captain's cribbage team 0 is the captain alone. Sent after the SeatRoster
+    Teams(Vec<Vec<u8>>),
+
+    // The rules of the game about to start; sent after the SeatRoster and Teams. In Lowball the
+    // scores are announced as usual but are points against the player, and nobody is skunked. In
+    // five-card cribbage hands are dealt five cards and the play is only played once up to 31. In
+    // Auction cribbage the players bid for the crib after every deal. When hints are allowed
+    // players may ask for discard hints, and every other player is told when they do. In Captain's
+    // cribbage the captain sits in the given seat and plays as team 0, needing the given points to
+    // win instead; the skunk line of the captain is found from them the same way as the skunk line
+    // from the points to win
+    TableRules {
+        points_to_win: u8,
+        skunk_line: u8,
+        captain: Option<u8>,
+        captain_points_to_win: Option<u8>,
+        lowball: bool,
+        five_card: bool,
+        auction: bool,
+        hints: bool,
+    },
+
     // That the model is waiting for a confirmation event to process that player's initial cut
     WaitInitialCut,
 
-    // That the named player has cut the specified card in their initial cut
+    // That the player with the given index has cut the specified card in their initial cut
     InitialCutResult {
-        name: String,
+        player: u8,
         card: cribbage::deck::Card,
     },
 
-    // That the named player has been decided to be the first dealer as per the cut
-    InitialCutSuccess(String),
+    // That the player with the given index has been decided to be the first dealer as per the cut
+    InitialCutSuccess(u8),
 
     // That the cut has resulted in a tie and that it must be redone
     InitialCutFailure,
@@ -76,15 +158,47 @@ pub enum GameToClient {
     // That the player's hand is the included vector
     DealtHand(Vec<cribbage::deck::Card>),
 
+    // That the dealer has dealt the given number of cards from the deck face down straight to the
+    // crib, as with three players where the crib is one kitty card and a discard from each player
+    KittyDealt(u8),
+
+    // That the game is waiting for the player's bid for the crib in Auction cribbage
+    WaitBid,
+
+    // That the bid given by the client was rejected for the included reason; followed by another
+    // WaitBid
+    BidRejected(String),
+
+    // That the player with the given index has placed their bid
+    BidPlaced(u8),
+
+    // That the player with the given index won the crib with the given bid, which is taken from
+    // their score; they are the dealer in effect for the hand, so they play and show last, own the
+    // crib, and take his heels
+    AuctionResult {
+        player: u8,
+        bid: u8,
+    },
+
     // That the game is waiting for a discard selection of one card
     WaitDiscardOne,
     // That the game is waiting for a discard selection of two cards
     WaitDiscardTwo,
 
-    // That someone has discarded their card
-    DiscardPlacedOne(String),
-    // That someone has discarded their cards
-    DiscardPlacedTwo(String),
+    // That the player with the given index has discarded their card
+    DiscardPlacedOne(u8),
+    // That the player with the given index has discarded their cards
+    DiscardPlacedTwo(u8),
+
+    // The ways to discard from the player's hand ranked from best to worst, in answer to a
+    // RequestHint
+    DiscardHints(Vec<super::hints::DiscardHint>),
+
+    // That a RequestHint was denied for the included reason
+    HintDenied(String),
+
+    // That the player with the given index has been given discard hints
+    HintUsed(u8),
 
     // That all discards have been placed
     AllDiscards,
@@ -92,8 +206,8 @@ pub enum GameToClient {
     // That the game is waiting for confirmation to cut the starter card
     WaitCutStarter,
 
-    // That the starter card has been cut and the name of the player who cut it and its value
-    CutStarter(String, cribbage::deck::Card),
+    // That the starter card has been cut and the index of the player who cut it and its value
+    CutStarter(u8, cribbage::deck::Card),
 
     // That the game is waiting to know whether the dealer calls nibs or not
     WaitNibs,
@@ -101,17 +215,38 @@ pub enum GameToClient {
     // That the dealer has cut a jack and received two points
     Nibs,
 
-    // That a player has played a card and the following ScoreEvents have been claimed
+    // That the player with the given index has played a card and pegged the given points with it
     CardPlayed {
-        name: String,
+        player: u8,
         card: cribbage::deck::Card,
-        scores: Vec<cribbage::score::ScoreEvent>,
+        points: u8,
+    },
+
+    // That nobody could play on the count, or every card has been played, so the player with the
+    // given index pegs the given points for the go or for the last card; the count starts again
+    // from zero
+    Go {
+        player: u8,
+        points: u8,
     },
 
     // That the game is waiting for a player to place a card and that the valid indices are as
-    // listed
+    // listed; a player who can not play is passed over instead
     WaitPlay(Vec<u8>),
 
+    // That the player with the given index has counted the given hand, or crib, with the starter
+    // for the given scores in the show
+    ShowHand {
+        player: u8,
+        hand: Vec<cribbage::deck::Card>,
+        scores: Vec<cribbage::score::ScoreEvent>,
+    },
+    ShowCrib {
+        player: u8,
+        crib: Vec<cribbage::deck::Card>,
+        scores: Vec<cribbage::score::ScoreEvent>,
+    },
+
     // That the game is waiting for ScoreEvents for the previous play
     WaitPlayScore,
 
@@ -121,8 +256,94 @@ pub enum GameToClient {
     // That the game has rejected the scoring because the scores are incomplete
     IncompletePlayScoring,
 
-    // That the scores are as follows; contains a vector of pairs of names and scores
-    ScoreUpdate(Vec<(String, u8)>),
+    // That the game is waiting for the ScoreEvents the player finds in the given hand, or crib
+    // when is_crib is true, with the starter in the show; answered with a PlayScore
+    WaitShowScore {
+        cards: Vec<cribbage::deck::Card>,
+        is_crib: bool,
+    },
+
+    // That the player with the given index has taken the given points missed by an opponent in
+    // their count
+    Muggins {
+        player: u8,
+        points: u8,
+    },
+
+    // That the scores are as follows; contains a vector of pairs of player indices and scores, or
+    // of team indices and team scores when the players are partnered
+    ScoreUpdate(Vec<(u8, u8)>),
+
+    // That the player, or team when the players are partnered, with the given index has won the
+    // game. In Lowball that is the side with the fewest points, and of sides tied on the fewest
+    // points the one that shows its hand first from the player after the dealer
+    GameWinner(u8),
+
+    // The game wins of each player in the match; contains a vector of pairs of player indices, or
+    // team indices when the players are partnered, and game wins. Sent after each game of a match
+    MatchScore(Vec<(u8, u8)>),
+
+    // That the player, or team when the players are partnered, with the given index has won the
+    // match
+    MatchWinner(u8),
+
+    // That the next game of the match, with the given number counting from one, has started and
+    // is first dealt by the player with the given index instead of by an initial cut
+    NewGame {
+        number: u8,
+        dealer: u8,
+    },
+
+    // That the practice table is waiting for the player to place the given card in an empty square
+    // of their Cribbage Squares grid
+    WaitPlacement(cribbage::deck::Card),
+
+    // That the placement given by the client was rejected for the included reason; followed by
+    // another WaitPlacement
+    PlacementRejected(String),
+
+    // That the Cribbage Squares grid is full and has been counted; contains the grid in order by
+    // row, the starter, the points of each row and of each column, and their total
+    SquaresResult {
+        grid: Vec<Vec<cribbage::deck::Card>>,
+        starter: cribbage::deck::Card,
+        rows: Vec<u8>,
+        columns: Vec<u8>,
+        total: u16,
+    },
+
+    // That the practice table is waiting for confirmation to deal another grid
+    WaitNewGrid,
+
+    // That the practice table is waiting for the player to count the given hand with the given
+    // starter and send the scores they find in a PlayScore
+    WaitHandCount {
+        hand: Vec<cribbage::deck::Card>,
+        starter: cribbage::deck::Card,
+    },
+
+    // That the scores claimed for the hand have been checked; contains the claims that were right,
+    // the claims that were wrong, the scores in the hand that were not claimed, and the accuracy of
+    // the player's counting over every hand so far. Followed by the next WaitHandCount
+    HandCountResult {
+        right: Vec<cribbage::score::ScoreEvent>,
+        wrong: Vec<cribbage::score::ScoreEvent>,
+        missed: Vec<cribbage::score::ScoreEvent>,
+        accuracy: super::practice::CountingAccuracy,
+    },
+
+    // The statistics of the account with the given username, or None if it has no recorded games
+    Stats {
+        player: String,
+        stats: Option<super::stats::StatsReport>,
+    },
+
+    // The rated accounts from highest to lowest rating
+    Leaderboard(Vec<super::stats::LeaderboardEntry>),
+
+    // The entrants of the tournament the table is part of from best to worst record, or None if
+    // the table is not part of a tournament
+    Standings(Option<Vec<super::tournament::Standing>>),
 
     // That an error has occured
     Error(String),
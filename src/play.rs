@@ -0,0 +1,395 @@
+extern crate cribbage;
+use serde::{Deserialize, Serialize};
+
+// A count the table is waiting for a player to claim the points of when it is played with manual
+// scoring
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Claim {
+    // Two for his heels, which the dealer must call when the starter is a jack
+    Heels {
+        seat: u8,
+    },
+
+    // The points made by the card the seat just played
+    Card {
+        seat: u8,
+        card: cribbage::deck::Card,
+        points: u8,
+    },
+
+    // The seat's hand, or the crib they own, with the starter in the show
+    Show {
+        seat: u8,
+        is_crib: bool,
+    },
+}
+
+// The play of a single hand, which the table runs itself as the game object is only used up to the
+// cut of the starter. The play starts with the seat after the dealer and goes around every seat,
+// each playing a card without taking the count past thirty-one. A seat that can not play says go
+// and is passed over, and when nobody can play the last seat to play pegs one for the go and the
+// count starts again from zero with the seat after them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayState {
+    // The hand each seat kept after discarding and whether or not each of its cards has been
+    // played; the hands are left whole for the show
+    hands: Vec<Vec<cribbage::deck::Card>>,
+    played: Vec<Vec<bool>>,
+
+    // The cards played since the count last started from zero
+    pile: Vec<cribbage::deck::Card>,
+
+    // The dealer in effect for the hand, who plays and counts last and owns the crib; the winner of
+    // the crib in Auction cribbage
+    pub dealer: u8,
+
+    // The seat whose turn it is and the seat that played the last card, if any
+    pub turn: u8,
+    last_player: Option<u8>,
+
+    // Whether the play ends the first time nobody can play or the count reaches thirty-one, as in
+    // five-card cribbage, instead of going on until every card is played
+    single_count: bool,
+
+    // The points each seat has pegged in the play so far
+    pub pegged: Vec<u8>,
+
+    // The hands still to be counted in the show as pairs of the seat counting and whether or not
+    // it is the crib they own, in the order they are counted; empty until the play is over
+    pub shows: Vec<(u8, bool)>,
+
+    // The count waiting to be claimed, if any
+    pub claim: Option<Claim>,
+}
+
+impl PlayState {
+    pub fn new(hands: Vec<Vec<cribbage::deck::Card>>, dealer: u8, single_count: bool) -> PlayState {
+        let num_players = hands.len();
+        PlayState {
+            played: hands.iter().map(|hand| vec![false; hand.len()]).collect(),
+            hands: hands,
+            pile: Vec::new(),
+            dealer: dealer,
+            turn: (dealer + 1) % num_players as u8,
+            last_player: None,
+            single_count: single_count,
+            pegged: vec![0; num_players],
+            shows: Vec::new(),
+            claim: None,
+        }
+    }
+
+    // The count of the cards played since it last started from zero
+    pub fn count(&self) -> u8 {
+        count(&self.pile)
+    }
+
+    // The indices in the seat's hand of the cards it could play without taking the count past
+    // thirty-one
+    pub fn playable(&self, seat: u8) -> Vec<u8> {
+        let count = self.count();
+        self.hands[seat as usize]
+            .iter()
+            .enumerate()
+            .filter(|(index, card)| {
+                !self.played[seat as usize][*index] && count + count_value(card) <= 31
+            })
+            .map(|(index, _)| index as u8)
+            .collect()
+    }
+
+    // Plays the card at the given index in the seat's hand and returns it with the points it made,
+    // which the seat pegs once they are settled; returns the reason the card can not be played as
+    // an error
+    pub fn play(&mut self, seat: u8, index: u8) -> Result<(cribbage::deck::Card, u8), String> {
+        if seat != self.turn {
+            return Err("It is not your turn to play".to_string());
+        }
+        if !self.playable(seat).contains(&index) {
+            return Err("That card can not be played".to_string());
+        }
+
+        let card = self.hands[seat as usize][index as usize].clone();
+        self.played[seat as usize][index as usize] = true;
+        self.pile.push(card.clone());
+        self.last_player = Some(seat);
+
+        Ok((card, pile_points(&self.pile)))
+    }
+
+    // Moves the play on after a card is played. Returns the seat that pegs for the go or last
+    // card, if any, with the points, and the seat to play next, or None once the play is over
+    pub fn next(&mut self) -> (Option<(u8, u8)>, Option<u8>) {
+        let num_players = self.hands.len() as u8;
+
+        // A seat that can play goes next, in turn order; the seat that just played included, as
+        // it keeps playing while everyone else says go
+        if let Some(seat) = (1..=num_players)
+            .map(|offset| (self.turn + offset) % num_players)
+            .find(|seat| !self.playable(*seat).is_empty())
+        {
+            self.turn = seat;
+            return (None, Some(seat));
+        }
+
+        // Nobody can play, so the last seat to play pegs one for the go or for the last card,
+        // unless they reached thirty-one and were given two for it already
+        let go = match self.last_player {
+            Some(seat) if !self.pile.is_empty() && self.count() != 31 => {
+                self.peg(seat, 1);
+                Some((seat, 1))
+            }
+            _ => None,
+        };
+        self.pile.clear();
+
+        let any_left = self.played.iter().any(|hand| hand.contains(&false));
+        if self.single_count || !any_left {
+            return (go, None);
+        }
+
+        // The seat after the last to play leads the next count
+        let last_player = self.last_player.unwrap_or(self.turn);
+        let next = (1..=num_players)
+            .map(|offset| (last_player + offset) % num_players)
+            .find(|seat| !self.playable(*seat).is_empty());
+        if let Some(seat) = next {
+            self.turn = seat;
+        }
+        (go, next)
+    }
+
+    // Adds to the points the seat has pegged in the play
+    pub fn peg(&mut self, seat: u8, points: u8) {
+        self.pegged[seat as usize] = self.pegged[seat as usize].saturating_add(points);
+    }
+
+    // Lines up the hands for the show once the play is over; every hand from the seat after the
+    // dealer around to the dealer, then the dealer's crib
+    pub fn start_show(&mut self) {
+        self.shows = show_order(self.dealer, self.hands.len() as u8)
+            .into_iter()
+            .map(|seat| (seat, false))
+            .collect();
+        self.shows.push((self.dealer, true));
+    }
+}
+
+// The value of a card from one for an ace to thirteen for a king, used for pairs and runs
+fn rank(card: &cribbage::deck::Card) -> u8 {
+    match &card.value {
+        cribbage::deck::CardValue::Ace => 1,
+        cribbage::deck::CardValue::Two => 2,
+        cribbage::deck::CardValue::Three => 3,
+        cribbage::deck::CardValue::Four => 4,
+        cribbage::deck::CardValue::Five => 5,
+        cribbage::deck::CardValue::Six => 6,
+        cribbage::deck::CardValue::Seven => 7,
+        cribbage::deck::CardValue::Eight => 8,
+        cribbage::deck::CardValue::Nine => 9,
+        cribbage::deck::CardValue::Ten => 10,
+        cribbage::deck::CardValue::Jack => 11,
+        cribbage::deck::CardValue::Queen => 12,
+        cribbage::deck::CardValue::King => 13,
+    }
+}
+
+// The value a card counts for in the count of the play; face cards count ten
+fn count_value(card: &cribbage::deck::Card) -> u8 {
+    rank(card).min(10)
+}
+
+// The count of the cards played since the count last started from zero
+fn count(pile: &Vec<cribbage::deck::Card>) -> u8 {
+    pile.iter().map(count_value).sum()
+}
+
+// The points made by the last card played onto the pile, the cards played since the count last
+// started from zero; two for fifteen or thirty-one, two, six, or twelve for a pair, pair royal, or
+// double pair royal of the last cards played, and a point a card for the longest run the last cards
+// make in any order. The go and the last card are pegged by next
+fn pile_points(pile: &Vec<cribbage::deck::Card>) -> u8 {
+    let last = match pile.last() {
+        Some(last) => last,
+        None => return 0,
+    };
+
+    let mut points = match count(pile) {
+        15 | 31 => 2,
+        _ => 0,
+    };
+
+    points += match pile
+        .iter()
+        .rev()
+        .take_while(|card| rank(card) == rank(last))
+        .count()
+    {
+        0 | 1 => 0,
+        2 => 2,
+        3 => 6,
+        _ => 12,
+    };
+
+    let is_run = |cards: &[cribbage::deck::Card]| {
+        let mut ranks: Vec<u8> = cards.iter().map(rank).collect();
+        ranks.sort();
+        ranks.windows(2).all(|pair| pair[1] == pair[0] + 1)
+    };
+    if let Some(length) = (3..=pile.len())
+        .rev()
+        .find(|length| is_run(&pile[pile.len() - length..]))
+    {
+        points += length as u8;
+    }
+
+    points
+}
+
+// The order hands are counted in the show; from the seat after the dealer around to the dealer,
+// whose crib is counted after their hand
+pub fn show_order(dealer: u8, num_players: u8) -> Vec<u8> {
+    (1..=num_players)
+        .map(|offset| (dealer + offset) % num_players)
+        .collect()
+}
+
+// Matches the scores a player claimed for a count against the scores the game library finds in
+// it; returns the claims that are among those scores, the claims that are not, and the scores
+// that were not claimed. Each score can only be matched by one claim, so a score claimed twice is
+// right once and wrong once
+pub fn match_claims(
+    claims: Vec<cribbage::score::ScoreEvent>,
+    scores: Vec<cribbage::score::ScoreEvent>,
+) -> (
+    Vec<cribbage::score::ScoreEvent>,
+    Vec<cribbage::score::ScoreEvent>,
+    Vec<cribbage::score::ScoreEvent>,
+) {
+    let mut missed = scores;
+    let mut right = Vec::new();
+    let mut wrong = Vec::new();
+    for claim in claims {
+        match missed.iter().position(|score| *score == claim) {
+            Some(index) => right.push(missed.remove(index)),
+            None => wrong.push(claim),
+        }
+    }
+
+    (right, wrong, missed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(cards: &str) -> Vec<cribbage::deck::Card> {
+        cards
+            .split_whitespace()
+            .map(|card| super::super::deck::parse_card(card).unwrap())
+            .collect()
+    }
+
+    // Plays the first playable card of whoever's turn it is until the play is over, returning the
+    // seats in the order they played and the go scores
+    fn play_out(play: &mut PlayState) -> (Vec<u8>, Vec<(u8, u8)>) {
+        let mut order = Vec::new();
+        let mut gos = Vec::new();
+        let mut turn = Some(play.turn);
+        while let Some(seat) = turn {
+            let index = play.playable(seat)[0];
+            let (_, points) = play.play(seat, index).unwrap();
+            play.peg(seat, points);
+            order.push(seat);
+            let (go, next) = play.next();
+            if let Some(go) = go {
+                gos.push(go);
+            }
+            turn = next;
+        }
+        (order, gos)
+    }
+
+    #[test]
+    fn play_starts_after_the_dealer_and_goes_around_every_seat() {
+        let hands = vec![
+            hand("AC 2C 3C 4C"),
+            hand("AD 2D 3D 4D"),
+            hand("AH 2H 3H 4H"),
+            hand("AS 2S 3S 4S"),
+            hand("5C 5D 5H 5S"),
+        ];
+        let mut play = PlayState::new(hands, 4, false);
+        assert_eq!(play.turn, 0);
+        let (order, _) = play_out(&mut play);
+        assert_eq!(&order[..5], &[0, 1, 2, 3, 4]);
+        assert_eq!(order.len(), 20);
+    }
+
+    #[test]
+    fn go_goes_to_the_last_seat_to_play() {
+        // The tens make thirty and the two would make thirty-two, so seat 0 pegs the go
+        let hands = vec![hand("TC TH"), hand("TD 2D")];
+        let mut play = PlayState::new(hands, 1, false);
+        assert_eq!(play.play(0, 0).unwrap().1, 0);
+        assert_eq!(play.next(), (None, Some(1)));
+        play.play(1, 0).unwrap();
+        assert_eq!(play.next().1, Some(0));
+        play.play(0, 1).unwrap();
+        let (go, next) = play.next();
+        assert_eq!(go, Some((0, 1)));
+
+        // The count starts again with the seat after the one that pegged the go
+        assert_eq!(next, Some(1));
+        play.play(1, 1).unwrap();
+        let (last, next) = play.next();
+        assert_eq!(last, Some((1, 1)));
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn thirty_one_is_not_also_a_go() {
+        let hands = vec![hand("TC AC"), hand("KD QD")];
+        let mut play = PlayState::new(hands, 1, false);
+        let (order, gos) = play_out(&mut play);
+        assert_eq!(order, vec![0, 1, 0, 1]);
+        assert!(gos.is_empty());
+        assert_eq!(play.pegged, vec![0, 2]);
+    }
+
+    #[test]
+    fn single_count_ends_at_the_first_go() {
+        let hands = vec![hand("TC TH 5C"), hand("TD 9D 8D")];
+        let mut play = PlayState::new(hands, 1, true);
+        let (order, gos) = play_out(&mut play);
+        assert_eq!(order, vec![0, 1, 0]);
+        assert_eq!(gos, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn play_scores_pairs_fifteens_runs_and_thirty_one() {
+        assert_eq!(pile_points(&hand("7C 8D")), 2);
+        assert_eq!(pile_points(&hand("4C 4D 4H")), 6);
+        assert_eq!(pile_points(&hand("KC QD AH")), 0);
+        assert_eq!(pile_points(&hand("KC QD 5H 6S")), 2);
+        assert_eq!(pile_points(&hand("3C 5D 4H")), 3);
+        assert_eq!(pile_points(&hand("3C 5D 4H 6S")), 4);
+        assert_eq!(pile_points(&hand("TC 9D 5H 6S")), 0);
+    }
+
+    #[test]
+    fn show_counts_the_dealer_last() {
+        assert_eq!(show_order(1, 4), vec![2, 3, 0, 1]);
+        assert_eq!(show_order(0, 2), vec![1, 0]);
+    }
+
+    #[test]
+    fn crib_is_shown_last_for_the_dealer() {
+        let mut play = PlayState::new(vec![hand("AC"), hand("AD"), hand("AH")], 1, false);
+        play.start_show();
+        assert_eq!(
+            play.shows,
+            vec![(2, false), (0, false), (1, false), (1, true)]
+        );
+    }
+}
@@ -0,0 +1,514 @@
+extern crate cribbage;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc;
+use std::time;
+
+use super::game::{send_message, GameClientInterface, GciState};
+
+// The number of rows and columns in a Cribbage Squares grid
+const GRID_SIZE: usize = 4;
+
+// How long the table waits for a new client before checking the seated player for input again
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(10);
+
+// The solo practice modes a table may be opened in instead of a game; a practice table seats a
+// single player and keeps no log, snapshot, or statistics
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PracticeMode {
+    // Cribbage Squares; the player places sixteen cards one at a time in a four by four grid, then
+    // every row and column is counted as a hand with a shared starter
+    Squares,
+
+    // Counting drills; the player is dealt a hand and starter over and over, claims the scores
+    // they find in it, and is told which claims were right, which were wrong, and which scores
+    // they missed
+    Counting,
+}
+
+impl PracticeMode {
+    // Parses the mode as given on the command line
+    pub fn parse(mode: &str) -> Result<PracticeMode, String> {
+        match mode {
+            "squares" => Ok(PracticeMode::Squares),
+            "counting" => Ok(PracticeMode::Counting),
+            _ => Err(format!(
+                "Unknown practice mode {}; expected squares or counting",
+                mode
+            )),
+        }
+    }
+}
+
+// A Cribbage Squares grid being filled, and the rest of the deck the cards and starter are taken
+// from
+struct SquaresGrid {
+    squares: Vec<Vec<Option<cribbage::deck::Card>>>,
+    deck: Vec<cribbage::deck::Card>,
+}
+
+impl SquaresGrid {
+    fn new(deck: Vec<cribbage::deck::Card>) -> SquaresGrid {
+        SquaresGrid {
+            squares: vec![vec![None; GRID_SIZE]; GRID_SIZE],
+            deck: deck,
+        }
+    }
+
+    // The card the player is to place next
+    fn next_card(&self) -> cribbage::deck::Card {
+        self.deck[0].clone()
+    }
+
+    // Places the next card in the given square; returns the reason the placement is rejected as an
+    // error
+    fn place(&mut self, row: u8, column: u8) -> Result<(), String> {
+        let (row, column) = (row as usize, column as usize);
+        if row >= GRID_SIZE || column >= GRID_SIZE {
+            return Err("That square is not in the grid".to_string());
+        }
+        if self.squares[row][column].is_some() {
+            return Err("That square already has a card".to_string());
+        }
+
+        self.squares[row][column] = Some(self.deck.remove(0));
+        Ok(())
+    }
+
+    fn is_full(&self) -> bool {
+        self.squares.iter().flatten().all(|square| square.is_some())
+    }
+
+    // Cuts the starter and counts each row and column as a hand with it; only called once the
+    // grid is full
+    fn count(&self) -> super::messages::GameToClient {
+        let starter = self.deck[0].clone();
+        let grid: Vec<Vec<cribbage::deck::Card>> = self
+            .squares
+            .iter()
+            .map(|row| row.iter().map(|square| square.clone().unwrap()).collect())
+            .collect();
+
+        let rows: Vec<u8> = grid.iter().map(|row| hand_points(row, &starter)).collect();
+        let columns: Vec<u8> = (0..GRID_SIZE)
+            .map(|column| {
+                let cards = grid.iter().map(|row| row[column].clone()).collect();
+                hand_points(&cards, &starter)
+            })
+            .collect();
+        let total = rows
+            .iter()
+            .chain(columns.iter())
+            .map(|points| *points as u16)
+            .sum();
+
+        super::messages::GameToClient::SquaresResult {
+            grid: grid,
+            starter: starter,
+            rows: rows,
+            columns: columns,
+            total: total,
+        }
+    }
+}
+
+// The points a row or column of the grid counts for with the starter, counted as a hand rather
+// than a crib
+fn hand_points(hand: &Vec<cribbage::deck::Card>, starter: &cribbage::deck::Card) -> u8 {
+    cribbage::score::score_hand(hand, starter, false)
+        .iter()
+        .map(|event| event.point_value())
+        .sum()
+}
+
+// Deals a fresh grid and asks the player to place its first card
+fn start_grid(
+    deck_shuffler: &mut super::deck::DeckShuffler,
+    gci: &mut GameClientInterface,
+) -> SquaresGrid {
+    let grid = SquaresGrid::new(deck_shuffler.next_deck());
+    gci.state = GciState::WaitingForPlacement;
+    send_message(
+        super::messages::GameToClient::WaitPlacement(grid.next_card()),
+        gci,
+    );
+    grid
+}
+
+// The running accuracy of the player at a counting practice table
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CountingAccuracy {
+    // The hands counted, and how many of them were counted exactly with every score claimed and
+    // no wrong claims
+    pub hands_counted: u32,
+    pub hands_exact: u32,
+
+    // The claims that were right and wrong, and the scores that were missed, over every hand
+    pub claims_right: u32,
+    pub claims_wrong: u32,
+    pub scores_missed: u32,
+}
+
+// A hand and starter dealt to be counted
+struct HandCount {
+    hand: Vec<cribbage::deck::Card>,
+    starter: cribbage::deck::Card,
+}
+
+impl HandCount {
+    // Deals four cards and a starter from a fresh deck
+    fn deal(deck_shuffler: &mut super::deck::DeckShuffler) -> HandCount {
+        let mut deck = deck_shuffler.next_deck();
+        let starter = deck.remove(4);
+        deck.truncate(4);
+        HandCount {
+            hand: deck,
+            starter: starter,
+        }
+    }
+
+    // The message asking the player to count the hand
+    fn request(&self) -> super::messages::GameToClient {
+        super::messages::GameToClient::WaitHandCount {
+            hand: self.hand.clone(),
+            starter: self.starter.clone(),
+        }
+    }
+
+    // Checks the scores claimed by the player against the scores the game library finds in the
+    // hand and adds the result to their accuracy. A claim is right when it is one of those scores
+    fn check(
+        &self,
+        claims: Vec<cribbage::score::ScoreEvent>,
+        accuracy: &mut CountingAccuracy,
+    ) -> super::messages::GameToClient {
+        let (right, wrong, missed) = super::play::match_claims(
+            claims,
+            cribbage::score::score_hand(&self.hand, &self.starter, false),
+        );
+
+        accuracy.hands_counted += 1;
+        if wrong.is_empty() && missed.is_empty() {
+            accuracy.hands_exact += 1;
+        }
+        accuracy.claims_right += right.len() as u32;
+        accuracy.claims_wrong += wrong.len() as u32;
+        accuracy.scores_missed += missed.len() as u32;
+
+        super::messages::GameToClient::HandCountResult {
+            right: right,
+            wrong: wrong,
+            missed: missed,
+            accuracy: accuracy.clone(),
+        }
+    }
+}
+
+// Deals the first grid or hand of the practice mode once the player is seated
+fn start_practice(
+    mode: &PracticeMode,
+    deck_shuffler: &mut super::deck::DeckShuffler,
+    gci: &mut GameClientInterface,
+    grid: &mut Option<SquaresGrid>,
+    hand_count: &mut Option<HandCount>,
+) {
+    match mode {
+        PracticeMode::Squares => *grid = Some(start_grid(deck_shuffler, gci)),
+        PracticeMode::Counting => {
+            let dealt = HandCount::deal(deck_shuffler);
+            gci.state = GciState::WaitingForHandCount;
+            send_message(dealt.request(), gci);
+            *hand_count = Some(dealt);
+        }
+    }
+}
+
+// Handles a practice table in place of the game thread; the first client to greet the table is
+// seated once they give a name or log in, and any later clients are denied
+pub fn handle_practice(
+    // The settings of the table
+    config: super::config::TableConfig,
+    // Facilitates communication between the main thread and the practice thread
+    main_receiver: mpsc::Receiver<super::messages::MainToGame>,
+    main_transmitter: mpsc::Sender<super::messages::GameToMain>,
+) {
+    let mut client_interfaces: Vec<GameClientInterface> = Vec::new();
+    let mut deck_shuffler = super::deck::DeckShuffler::new(config.deck_setup.clone());
+
+    // The grid being filled or the hand being counted once the player is seated, and the accuracy
+    // of the player's counting so far
+    let mut grid: Option<SquaresGrid> = None;
+    let mut hand_count: Option<HandCount> = None;
+    let mut accuracy = CountingAccuracy::default();
+
+    // Only practice tables are handled here
+    let mode = config.practice.clone().unwrap();
+
+    loop {
+        // If there is a new client handler thread, create the GameClientInterface; waiting for one
+        // keeps the loop from spinning while the player thinks
+        match main_receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(super::messages::MainToGame::NewClient {
+                transmitter,
+                receiver,
+            }) => {
+                client_interfaces.push(GameClientInterface::new(transmitter, receiver));
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            _ => {}
+        }
+
+        let mut is_seat_taken = client_interfaces.iter().any(|gci| gci.index.is_some());
+
+        for gci in &mut client_interfaces {
+            let message = match gci.try_receive() {
+                Ok(message) => message,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    gci.state = GciState::Disconnected;
+                    continue;
+                }
+                Err(mpsc::TryRecvError::Empty) => continue,
+            };
+
+            match (gci.state.clone(), message) {
+                // Seat the first client to greet the table and deny everyone else
+                (GciState::Connecting, super::messages::ClientToGame::Greeting) => {
+                    if is_seat_taken {
+                        gci.state = GciState::Watching;
+                        send_message(super::messages::GameToClient::DeniedTableFull, gci);
+                    } else if config.require_login {
+                        is_seat_taken = true;
+                        gci.index = Some(0);
+                        gci.state = GciState::WaitingLogin;
+                        send_message(super::messages::GameToClient::WaitLogin, gci);
+                    } else {
+                        is_seat_taken = true;
+                        gci.index = Some(0);
+                        gci.state = GciState::WaitingName;
+                        send_message(super::messages::GameToClient::WaitName, gci);
+                    }
+                }
+
+                // Accept the name or login of the player, then deal the first grid or hand
+                (state @ GciState::WaitingName, message)
+                | (state @ GciState::WaitingLogin, message) => {
+                    let request = if state == GciState::WaitingLogin {
+                        super::messages::GameToClient::WaitLogin
+                    } else {
+                        super::messages::GameToClient::WaitName
+                    };
+
+                    let name = match message {
+                        super::messages::ClientToGame::Name(name)
+                            if state == GciState::WaitingName =>
+                        {
+                            super::game::check_unregistered(name.trim(), &config.accounts_path)
+                        }
+                        super::messages::ClientToGame::LoggedIn(username)
+                            if state == GciState::WaitingLogin =>
+                        {
+                            Ok(username)
+                        }
+                        _ => {
+                            send_message(request, gci);
+                            continue;
+                        }
+                    };
+
+                    match name.and_then(|name| {
+                        super::game::check_name(&name)?;
+                        Ok(name)
+                    }) {
+                        Ok(name) => {
+                            send_message(
+                                super::messages::GameToClient::PlayerJoinNotification {
+                                    name: name,
+                                    number: 1,
+                                    of: 1,
+                                },
+                                gci,
+                            );
+                            start_practice(
+                                &mode,
+                                &mut deck_shuffler,
+                                gci,
+                                &mut grid,
+                                &mut hand_count,
+                            );
+                        }
+                        Err(reason) => {
+                            send_message(super::messages::GameToClient::NameRejected(reason), gci);
+                            send_message(request, gci);
+                        }
+                    }
+                }
+
+                // Place the card in the chosen square, then deal the next card or count the grid
+                // once it is full and ask whether to play another
+                (
+                    GciState::WaitingForPlacement,
+                    super::messages::ClientToGame::PlaceCard { row, column },
+                ) => {
+                    let current = grid.as_mut().unwrap();
+                    if let Err(reason) = current.place(row, column) {
+                        send_message(
+                            super::messages::GameToClient::PlacementRejected(reason),
+                            gci,
+                        );
+                    }
+
+                    if current.is_full() {
+                        send_message(current.count(), gci);
+                        gci.state = GciState::WaitingForNewGrid;
+                        send_message(super::messages::GameToClient::WaitNewGrid, gci);
+                    } else {
+                        send_message(
+                            super::messages::GameToClient::WaitPlacement(current.next_card()),
+                            gci,
+                        );
+                    }
+                }
+                (GciState::WaitingForPlacement, _) => send_message(
+                    super::messages::GameToClient::WaitPlacement(
+                        grid.as_ref().unwrap().next_card(),
+                    ),
+                    gci,
+                ),
+
+                (GciState::WaitingForNewGrid, super::messages::ClientToGame::Confirmation) => {
+                    grid = Some(start_grid(&mut deck_shuffler, gci));
+                }
+                (GciState::WaitingForNewGrid, _) => {
+                    send_message(super::messages::GameToClient::WaitNewGrid, gci)
+                }
+
+                // Check the scores claimed for the hand, then deal the next one
+                (
+                    GciState::WaitingForHandCount,
+                    super::messages::ClientToGame::PlayScore(claims),
+                ) => {
+                    send_message(
+                        hand_count.as_ref().unwrap().check(claims, &mut accuracy),
+                        gci,
+                    );
+                    let dealt = HandCount::deal(&mut deck_shuffler);
+                    send_message(dealt.request(), gci);
+                    hand_count = Some(dealt);
+                }
+                (GciState::WaitingForHandCount, _) => {
+                    send_message(hand_count.as_ref().unwrap().request(), gci)
+                }
+
+                // Watchers are only told again that the table is full
+                (_, _) if gci.index.is_none() => {
+                    send_message(super::messages::GameToClient::DeniedTableFull, gci)
+                }
+                (_, _) => {}
+            }
+        }
+
+        // Only the player leaving ends the table
+        if client_interfaces
+            .iter()
+            .any(|gci| gci.index.is_some() && gci.state == GciState::Disconnected)
+        {
+            break;
+        }
+    }
+
+    main_transmitter
+        .send(super::messages::GameToMain::EndServer)
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(cards: &str) -> Vec<cribbage::deck::Card> {
+        cards
+            .split_whitespace()
+            .map(|card| super::super::deck::parse_card(card).unwrap())
+            .collect()
+    }
+
+    // A hand with two fifteens, of the five and ten and of the five and king, and nothing else
+    fn hand_count() -> HandCount {
+        HandCount {
+            hand: cards("5H TC 3D 9S"),
+            starter: cards("KD")[0].clone(),
+        }
+    }
+
+    // The scores in the hand as the game library counts them
+    fn scores() -> Vec<cribbage::score::ScoreEvent> {
+        let hand_count = hand_count();
+        cribbage::score::score_hand(&hand_count.hand, &hand_count.starter, false)
+    }
+
+    // A score that is not in the hand; the fifteen of the seven and eight of another hand
+    fn other_score() -> cribbage::score::ScoreEvent {
+        let scores = cribbage::score::score_hand(&cards("7H 8S AC 2D"), &cards("KD")[0], false);
+        assert_eq!(scores.len(), 1);
+        scores[0].clone()
+    }
+
+    fn check(
+        claims: Vec<cribbage::score::ScoreEvent>,
+        accuracy: &mut CountingAccuracy,
+    ) -> (usize, usize, usize) {
+        match hand_count().check(claims, accuracy) {
+            super::super::messages::GameToClient::HandCountResult {
+                right,
+                wrong,
+                missed,
+                ..
+            } => (right.len(), wrong.len(), missed.len()),
+            _ => panic!("Check did not return a HandCountResult"),
+        }
+    }
+
+    #[test]
+    fn every_score_claimed_is_exact() {
+        let mut accuracy = CountingAccuracy::default();
+        assert_eq!(scores().len(), 2);
+        assert_eq!(check(scores(), &mut accuracy), (2, 0, 0));
+        assert_eq!((accuracy.hands_counted, accuracy.hands_exact), (1, 1));
+    }
+
+    #[test]
+    fn claims_match_in_any_order() {
+        let mut accuracy = CountingAccuracy::default();
+        let mut claims = scores();
+        claims.reverse();
+        assert_eq!(check(claims, &mut accuracy), (2, 0, 0));
+    }
+
+    #[test]
+    fn wrong_and_missed_scores_are_counted() {
+        let mut accuracy = CountingAccuracy::default();
+        assert_eq!(
+            check(vec![scores()[0].clone(), other_score()], &mut accuracy),
+            (1, 1, 1)
+        );
+        assert_eq!(check(Vec::new(), &mut accuracy), (0, 0, 2));
+        assert_eq!(accuracy.hands_counted, 2);
+        assert_eq!(accuracy.hands_exact, 0);
+        assert_eq!(
+            (
+                accuracy.claims_right,
+                accuracy.claims_wrong,
+                accuracy.scores_missed
+            ),
+            (1, 1, 3)
+        );
+    }
+
+    #[test]
+    fn score_claimed_twice_is_right_once() {
+        let mut accuracy = CountingAccuracy::default();
+        let mut claims = scores();
+        claims.push(claims[0].clone());
+        assert_eq!(check(claims, &mut accuracy), (2, 1, 0));
+        assert_eq!(accuracy.hands_exact, 0);
+    }
+}
@@ -0,0 +1,124 @@
+// The rating given to an account before it has played a ranked game
+pub const INITIAL_RATING: f64 = 1500.0;
+
+// How far a single game can move a rating; higher while an account has few games so that new
+// accounts settle near their true rating quickly
+const K_FACTOR_NEW: f64 = 40.0;
+const K_FACTOR: f64 = 20.0;
+
+// The number of ranked games after which an account uses the normal K factor
+const PROVISIONAL_GAMES: u32 = 20;
+
+// The expected score, between 0 and 1, of a side rated rating against a side rated
+// opponent_rating
+fn expected_score(rating: f64, opponent_rating: f64) -> f64 {
+    1.0 / (1.0 + 10.0_f64.powf((opponent_rating - rating) / 400.0))
+}
+
+// A player in a ranked game, with their rating and ranked games played before the game
+pub struct RatedPlayer {
+    pub rating: f64,
+    pub games_played: u32,
+}
+
+// Works out the new rating of every player in a game between two sides, where a side is a single
+// player or a team of partners. A team is rated as the average of its members, and each member
+// moves by the amount the team would scaled by their own K factor, so partners of different
+// experience are not dragged together. Returns the new ratings in the same order as the sides and
+// their players
+pub fn rate_game(winners: &Vec<RatedPlayer>, losers: &Vec<RatedPlayer>) -> (Vec<f64>, Vec<f64>) {
+    let average = |side: &Vec<RatedPlayer>| {
+        side.iter().map(|player| player.rating).sum::<f64>() / side.len() as f64
+    };
+    let winner_rating = average(winners);
+    let loser_rating = average(losers);
+
+    let k_factor = |player: &RatedPlayer| {
+        if player.games_played < PROVISIONAL_GAMES {
+            K_FACTOR_NEW
+        } else {
+            K_FACTOR
+        }
+    };
+
+    let winner_change = 1.0 - expected_score(winner_rating, loser_rating);
+    let loser_change = 0.0 - expected_score(loser_rating, winner_rating);
+
+    (
+        winners
+            .iter()
+            .map(|player| player.rating + k_factor(player) * winner_change)
+            .collect(),
+        losers
+            .iter()
+            .map(|player| player.rating + k_factor(player) * loser_change)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(rating: f64, games_played: u32) -> RatedPlayer {
+        RatedPlayer {
+            rating: rating,
+            games_played: games_played,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn expected_scores_add_up_to_one() {
+        assert_close(expected_score(1500.0, 1500.0), 0.5);
+        assert_close(expected_score(1900.0, 1500.0), 10.0 / 11.0);
+        assert_close(
+            expected_score(1650.0, 1480.0) + expected_score(1480.0, 1650.0),
+            1.0,
+        );
+    }
+
+    #[test]
+    fn two_players_of_equal_rating_move_by_half_the_k_factor() {
+        let (winners, losers) = rate_game(&vec![player(1500.0, 0)], &vec![player(1500.0, 50)]);
+        assert_close(winners[0], 1500.0 + K_FACTOR_NEW / 2.0);
+        assert_close(losers[0], 1500.0 - K_FACTOR / 2.0);
+    }
+
+    #[test]
+    fn upset_moves_ratings_further_than_expected_win() {
+        let (favourite_won, _) = rate_game(&vec![player(1900.0, 50)], &vec![player(1500.0, 50)]);
+        let (underdog_won, _) = rate_game(&vec![player(1500.0, 50)], &vec![player(1900.0, 50)]);
+        assert_close(favourite_won[0], 1900.0 + K_FACTOR / 11.0);
+        assert_close(underdog_won[0], 1500.0 + K_FACTOR * 10.0 / 11.0);
+    }
+
+    #[test]
+    fn teams_are_rated_as_their_average() {
+        // Both teams average 1500, so every player moves by half their own K factor
+        let winners = vec![player(1400.0, 50), player(1600.0, 5)];
+        let losers = vec![player(1300.0, 50), player(1700.0, 50)];
+        let (winner_ratings, loser_ratings) = rate_game(&winners, &losers);
+        assert_close(winner_ratings[0], 1400.0 + K_FACTOR / 2.0);
+        assert_close(winner_ratings[1], 1600.0 + K_FACTOR_NEW / 2.0);
+        assert_close(loser_ratings[0], 1300.0 - K_FACTOR / 2.0);
+        assert_close(loser_ratings[1], 1700.0 - K_FACTOR / 2.0);
+    }
+
+    #[test]
+    fn team_against_stronger_team_gains_more() {
+        let winners = vec![player(1400.0, 50), player(1400.0, 50)];
+        let losers = vec![player(1800.0, 50), player(1800.0, 50)];
+        let (winner_ratings, loser_ratings) = rate_game(&winners, &losers);
+        assert_close(winner_ratings[0], 1400.0 + K_FACTOR * 10.0 / 11.0);
+        assert_close(loser_ratings[1], 1800.0 - K_FACTOR * 10.0 / 11.0);
+    }
+}
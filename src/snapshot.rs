@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+// Everything needed to restore a table after the server process dies; the game object itself is
+// rebuilt by processing the first log_entries entries of the game log again
+#[derive(Serialize, Deserialize)]
+pub struct TableSnapshot {
+    // The settings the table was opened with
+    pub config: super::config::TableConfig,
+
+    // The number of entries in effect in the game log when the snapshot was taken
+    pub log_entries: usize,
+
+    // The name of the player in each seat, ie. GameClientInterface.index
+    pub seat_names: Vec<String>,
+
+    // The input each seat was asked for when the snapshot was taken
+    pub seat_states: Vec<super::game::GciState>,
+
+    // The account each seat is logged in to, if any, and the reconnect token given to each seat;
+    // a player takes their seat back by logging in to its account or by giving its token
+    pub seat_accounts: Vec<Option<String>>,
+    pub seat_tokens: Vec<String>,
+
+    // The inputs held for the next process_event call and the order inputs are being asked for in
+    pub input_store: super::game::InputStore,
+    pub input_tracker: Option<super::game::OrderedInputTracker>,
+
+    // The state of the generator giving the decks so that the decks after the restore are the
+    // same as they would have been
+    pub deck_shuffler: super::deck::DeckShuffler,
+
+    // The game wins of each seat and which game of the match is being played
+    pub match_state: super::match_play::MatchState,
+
+    // The play of the hand when the snapshot was taken during the play
+    pub play: Option<super::play::PlayState>,
+
+    // The seat that won the crib of the hand in Auction cribbage and deals in effect for it, if any
+    pub crib_owner: Option<u8>,
+
+    // The score of each seat; checked against the rebuilt game object when restoring
+    pub scores: Vec<u8>,
+}
+
+impl TableSnapshot {
+    // Writes the snapshot to a temporary file then renames it over the previous snapshot so that a
+    // crash while writing never leaves a partial snapshot behind
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let bytes = match bincode::serialize(self) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(format!("Could not serialize snapshot; {}", e)),
+        };
+
+        let temporary_path = format!("{}.tmp", path);
+        match fs::write(&temporary_path, bytes).and_then(|_| fs::rename(&temporary_path, path)) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Could not save snapshot {}; {}", path, e)),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<TableSnapshot, String> {
+        match fs::read(path) {
+            Ok(bytes) => match bincode::deserialize(&bytes) {
+                Ok(snapshot) => Ok(snapshot),
+                Err(e) => Err(format!("Invalid snapshot {}; {}", path, e)),
+            },
+            Err(e) => Err(format!("Could not read snapshot {}; {}", path, e)),
+        }
+    }
+}
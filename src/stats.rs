@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+// A player's record against a single opponent
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OpponentRecord {
+    pub games_played: u32,
+    pub wins: u32,
+}
+
+// Everything recorded about the games of a single account
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct PlayerStats {
+    // The username as it was registered, shown on the leaderboard
+    #[serde(default)]
+    username: String,
+
+    games_played: u32,
+    wins: u32,
+    skunks_given: u32,
+    skunks_received: u32,
+
+    // The hands counted by the player and the total and highest of their scores
+    hands_counted: u32,
+    hand_points: u32,
+    highest_hand: u8,
+
+    // The hands the player pegged in and the total of the points pegged
+    hands_pegged: u32,
+    pegging_points: u32,
+
+    // The player's record against each opponent by username
+    opponents: HashMap<String, OpponentRecord>,
+
+    // The player's rating, or None until they have played a ranked game, and the number of ranked
+    // games they have played
+    #[serde(default)]
+    rating: Option<f64>,
+    #[serde(default)]
+    ranked_games: u32,
+}
+
+// The statistics of an account as sent to clients, with averages worked out
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatsReport {
+    pub games_played: u32,
+    pub wins: u32,
+    pub skunks_given: u32,
+    pub skunks_received: u32,
+    pub average_hand: f32,
+    pub highest_hand: u8,
+    pub average_pegging: f32,
+    pub opponents: Vec<(String, OpponentRecord)>,
+    pub rating: Option<u16>,
+    pub ranked_games: u32,
+}
+
+// A single line of the leaderboard of rated accounts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub username: String,
+    pub rating: u16,
+    pub ranked_games: u32,
+}
+
+// The result of a single seat in a finished game
+pub struct SeatResult {
+    // The username of the account in the seat; seats without an account are not recorded
+    pub account: Option<String>,
+    // The side the seat played for; seats on the same side were partners
+    pub side: u8,
+    pub score: u8,
+    pub won: bool,
+}
+
+// The statistics of every account, kept in a local JSON file that is rewritten whenever a game is
+// recorded
+pub struct StatsStore {
+    path: String,
+
+    // Statistics by lowercase username to match the account store
+    players: HashMap<String, PlayerStats>,
+}
+
+impl StatsStore {
+    // Opens the store at the given path; a store that does not exist yet is created empty
+    pub fn open(path: &str) -> Result<StatsStore, String> {
+        let players = match fs::read(path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(players) => players,
+                Err(e) => return Err(format!("Could not read statistics {}; {}", path, e)),
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(format!("Could not open statistics {}; {}", path, e)),
+        };
+
+        Ok(StatsStore {
+            path: path.to_string(),
+            players: players,
+        })
+    }
+
+    // Records a finished game given the skunk line of each side in order by index; a loser below
+    // the skunk line of their side counts as a skunk given by every winner and received by that
+    // loser. Returns an error if the store could not be saved; the game is kept in memory and saved
+    // with the next one
+    pub fn record_game(
+        &mut self,
+        results: &Vec<SeatResult>,
+        skunk_lines: &Vec<u8>,
+    ) -> Result<(), String> {
+        let is_skunked =
+            |result: &SeatResult| !result.won && result.score < skunk_lines[result.side as usize];
+        let anyone_skunked = results.iter().any(|result| is_skunked(result));
+
+        for result in results {
+            let account = match &result.account {
+                Some(account) => account,
+                None => continue,
+            };
+
+            let stats = self
+                .players
+                .entry(account.to_lowercase())
+                .or_insert(PlayerStats::default());
+            stats.username = account.clone();
+            stats.games_played += 1;
+            if result.won {
+                stats.wins += 1;
+                if anyone_skunked {
+                    stats.skunks_given += 1;
+                }
+            } else if is_skunked(result) {
+                stats.skunks_received += 1;
+            }
+
+            // Only players on other sides are opponents; partners share the result
+            for opponent in results {
+                if opponent.side == result.side {
+                    continue;
+                }
+                if let Some(opponent_account) = &opponent.account {
+                    if opponent_account != account {
+                        let record = stats
+                            .opponents
+                            .entry(opponent_account.clone())
+                            .or_insert(OpponentRecord::default());
+                        record.games_played += 1;
+                        if result.won {
+                            record.wins += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.save()
+    }
+
+    // Updates the ratings of the accounts in a finished ranked game; saved with the next game
+    // recorded. Only games between exactly two sides in which every seat has an account are rated
+    pub fn record_ratings(&mut self, results: &Vec<SeatResult>) {
+        if results.iter().any(|result| result.account.is_none()) {
+            return;
+        }
+        let mut sides: Vec<u8> = results.iter().map(|result| result.side).collect();
+        sides.sort();
+        sides.dedup();
+        if sides.len() != 2 {
+            return;
+        }
+
+        // The lowercase usernames and ratings of the players on each side
+        let mut winner_keys = Vec::new();
+        let mut winners = Vec::new();
+        let mut loser_keys = Vec::new();
+        let mut losers = Vec::new();
+        for result in results {
+            let key = result.account.as_ref().unwrap().to_lowercase();
+            let stats = self
+                .players
+                .entry(key.clone())
+                .or_insert(PlayerStats::default());
+            let player = super::ratings::RatedPlayer {
+                rating: stats.rating.unwrap_or(super::ratings::INITIAL_RATING),
+                games_played: stats.ranked_games,
+            };
+            if result.won {
+                winner_keys.push(key);
+                winners.push(player);
+            } else {
+                loser_keys.push(key);
+                losers.push(player);
+            }
+        }
+
+        if winners.is_empty() || losers.is_empty() {
+            return;
+        }
+        let (winner_ratings, loser_ratings) = super::ratings::rate_game(&winners, &losers);
+        for (key, rating) in winner_keys
+            .iter()
+            .zip(winner_ratings)
+            .chain(loser_keys.iter().zip(loser_ratings))
+        {
+            let stats = self.players.get_mut(key).unwrap();
+            stats.rating = Some(rating);
+            stats.ranked_games += 1;
+        }
+    }
+
+    // Records the score of a hand counted by the player; saved with the next game recorded
+    pub fn record_hand(&mut self, account: &str, points: u8) {
+        let stats = self
+            .players
+            .entry(account.to_lowercase())
+            .or_insert(PlayerStats::default());
+        stats.hands_counted += 1;
+        stats.hand_points += points as u32;
+        if points > stats.highest_hand {
+            stats.highest_hand = points;
+        }
+    }
+
+    // Records the points pegged by the player over a single hand; saved with the next game
+    // recorded
+    pub fn record_pegging(&mut self, account: &str, points: u8) {
+        let stats = self
+            .players
+            .entry(account.to_lowercase())
+            .or_insert(PlayerStats::default());
+        stats.hands_pegged += 1;
+        stats.pegging_points += points as u32;
+    }
+
+    // Returns the statistics of the account to send to a client, or None if it has none
+    pub fn report(&self, account: &str) -> Option<StatsReport> {
+        match self.players.get(&account.trim().to_lowercase()) {
+            Some(stats) => Some(StatsReport {
+                games_played: stats.games_played,
+                wins: stats.wins,
+                skunks_given: stats.skunks_given,
+                skunks_received: stats.skunks_received,
+                average_hand: if stats.hands_counted > 0 {
+                    stats.hand_points as f32 / stats.hands_counted as f32
+                } else {
+                    0.0
+                },
+                highest_hand: stats.highest_hand,
+                average_pegging: if stats.hands_pegged > 0 {
+                    stats.pegging_points as f32 / stats.hands_pegged as f32
+                } else {
+                    0.0
+                },
+                opponents: stats
+                    .opponents
+                    .iter()
+                    .map(|(opponent, record)| (opponent.clone(), record.clone()))
+                    .collect(),
+                rating: stats.rating.map(|rating| rating.round() as u16),
+                ranked_games: stats.ranked_games,
+            }),
+            None => None,
+        }
+    }
+
+    // Returns the rated accounts from highest to lowest rating
+    pub fn leaderboard(&self) -> Vec<LeaderboardEntry> {
+        let mut entries: Vec<LeaderboardEntry> = self
+            .players
+            .values()
+            .filter_map(|stats| match stats.rating {
+                Some(rating) => Some(LeaderboardEntry {
+                    username: stats.username.clone(),
+                    rating: rating.round() as u16,
+                    ranked_games: stats.ranked_games,
+                }),
+                None => None,
+            })
+            .collect();
+        entries.sort_by(|a, b| b.rating.cmp(&a.rating).then(a.username.cmp(&b.username)));
+        entries
+    }
+
+    // Writes the store to a temporary file then renames it over the previous store so that a crash
+    // while writing never loses the statistics
+    pub fn save(&self) -> Result<(), String> {
+        let bytes = match serde_json::to_vec_pretty(&self.players) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(format!("Could not serialize statistics; {}", e)),
+        };
+
+        let temporary_path = format!("{}.tmp", self.path);
+        match fs::write(&temporary_path, bytes)
+            .and_then(|_| fs::rename(&temporary_path, &self.path))
+        {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Could not save statistics {}; {}", self.path, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(account: &str, side: u8, won: bool) -> SeatResult {
+        SeatResult {
+            account: Some(account.to_string()),
+            side: side,
+            score: if won { 121 } else { 100 },
+            won: won,
+        }
+    }
+
+    #[test]
+    fn partners_are_not_recorded_as_opponents() {
+        let path = std::env::temp_dir().join("cribbage_stats_partners_test.json");
+        let mut stats_store = StatsStore {
+            path: path.to_string_lossy().to_string(),
+            players: HashMap::new(),
+        };
+        let results = vec![
+            result("alice", 0, true),
+            result("bob", 1, false),
+            result("carol", 0, true),
+            result("dave", 1, false),
+        ];
+        stats_store.record_game(&results, &vec![91, 91]).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let mut opponents: Vec<String> = stats_store
+            .report("alice")
+            .unwrap()
+            .opponents
+            .into_iter()
+            .map(|(opponent, record)| {
+                assert_eq!((record.games_played, record.wins), (1, 1));
+                opponent
+            })
+            .collect();
+        opponents.sort();
+        assert_eq!(opponents, vec!["bob", "dave"]);
+
+        let dave = stats_store.report("dave").unwrap();
+        assert!(dave
+            .opponents
+            .iter()
+            .all(|(opponent, record)| { opponent != "bob" && record.wins == 0 }));
+        assert_eq!(dave.opponents.len(), 2);
+    }
+}
@@ -0,0 +1,566 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::process;
+
+// How the entrants of a tournament are paired
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Format {
+    // Entrants are out after their first loss
+    SingleElimination,
+
+    // Entrants are out after their second loss; entrants with one loss play each other until a
+    // single unbeaten and a single once beaten entrant remain to play the final
+    DoubleElimination,
+
+    // Every entrant plays every other entrant once
+    RoundRobin,
+}
+
+impl Format {
+    // Parses the format as given on the command line
+    pub fn parse(format: &str) -> Result<Format, String> {
+        match format {
+            "single" => Ok(Format::SingleElimination),
+            "double" => Ok(Format::DoubleElimination),
+            "roundrobin" => Ok(Format::RoundRobin),
+            _ => Err(format!(
+                "Unknown format {}; expected single, double, or roundrobin",
+                format
+            )),
+        }
+    }
+}
+
+// A single table in a round; a pairing of one player is a bye and is decided as soon as it is made
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pairing {
+    pub players: Vec<String>,
+    pub winner: Option<String>,
+}
+
+// The record of an entrant as sent to clients; byes are not counted as games
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    pub played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub eliminated: bool,
+}
+
+// The result of a table in a tournament, written by the table when its match is decided and read
+// by the tournament to advance the bracket
+#[derive(Serialize, Deserialize)]
+pub struct TableResult {
+    pub winner: String,
+}
+
+impl TableResult {
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        write_json(self, path)
+    }
+
+    pub fn load(path: &str) -> Result<TableResult, String> {
+        match fs::read(path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("Invalid table result {}; {}", path, e)),
+            },
+            Err(e) => Err(format!("Could not read table result {}; {}", path, e)),
+        }
+    }
+}
+
+// A tournament between entrants identified by account username, kept in a local JSON file that is
+// rewritten whenever a result is recorded. Round robin tournaments have every round made when the
+// tournament is created; elimination tournaments make each round once the last is decided
+#[derive(Serialize, Deserialize)]
+pub struct Tournament {
+    pub format: Format,
+
+    // The entrants in order of seeding
+    pub entrants: Vec<String>,
+
+    pub rounds: Vec<Vec<Pairing>>,
+}
+
+impl Tournament {
+    // Creates a tournament and makes its first round; returns an error if there are fewer than two
+    // entrants or an entrant is named twice
+    pub fn new(format: Format, entrants: Vec<String>) -> Result<Tournament, String> {
+        if entrants.len() < 2 {
+            return Err("A tournament needs at least two entrants".to_string());
+        }
+        for (index, entrant) in entrants.iter().enumerate() {
+            if entrants[..index]
+                .iter()
+                .any(|other| other.to_lowercase() == entrant.to_lowercase())
+            {
+                return Err(format!("{} is entered twice", entrant));
+            }
+        }
+
+        let mut tournament = Tournament {
+            format: format,
+            entrants: entrants,
+            rounds: Vec::new(),
+        };
+
+        match format {
+            Format::RoundRobin => tournament.rounds = round_robin(&tournament.entrants),
+            _ => tournament.rounds.push(pair_by_seed(&tournament.entrants)),
+        }
+
+        Ok(tournament)
+    }
+
+    // The index of the first round with an undecided pairing, or None if every round made so far
+    // has been decided
+    pub fn current_round(&self) -> Option<usize> {
+        self.rounds
+            .iter()
+            .position(|round| round.iter().any(|pairing| pairing.winner.is_none()))
+    }
+
+    // Records the winner of a pairing then makes the next round if the tournament needs one
+    pub fn record_result(
+        &mut self,
+        round: usize,
+        pairing: usize,
+        winner: &str,
+    ) -> Result<(), String> {
+        let pairing = match self
+            .rounds
+            .get_mut(round)
+            .and_then(|round| round.get_mut(pairing))
+        {
+            Some(pairing) => pairing,
+            None => return Err("No such pairing".to_string()),
+        };
+        if pairing.winner.is_some() {
+            return Err("Pairing has already been decided".to_string());
+        }
+        let winner = match pairing
+            .players
+            .iter()
+            .find(|player| player.to_lowercase() == winner.to_lowercase())
+        {
+            Some(winner) => winner.clone(),
+            None => return Err(format!("{} is not in the pairing", winner)),
+        };
+        pairing.winner = Some(winner);
+
+        if self.current_round().is_none() && !self.is_finished() {
+            let round = match self.format {
+                Format::SingleElimination => pair_by_seed(&self.remaining(1)),
+                Format::DoubleElimination => self.double_elimination_round(),
+                Format::RoundRobin => Vec::new(),
+            };
+            self.rounds.push(round);
+        }
+
+        Ok(())
+    }
+
+    // The number of games the entrant has lost
+    fn losses(&self, entrant: &str) -> u32 {
+        self.rounds
+            .iter()
+            .flatten()
+            .filter(|pairing| pairing.players.len() > 1)
+            .filter(|pairing| pairing.players.iter().any(|player| player == entrant))
+            .filter(|pairing| {
+                pairing.winner.is_some() && pairing.winner.as_deref() != Some(entrant)
+            })
+            .count() as u32
+    }
+
+    // The entrants with fewer than the given number of losses, in order of seeding
+    fn remaining(&self, losses_allowed: u32) -> Vec<String> {
+        self.entrants
+            .iter()
+            .filter(|entrant| self.losses(entrant) < losses_allowed)
+            .cloned()
+            .collect()
+    }
+
+    // Pairs the unbeaten entrants with each other and the once beaten entrants with each other,
+    // or the last unbeaten entrant with the last once beaten entrant in the final
+    fn double_elimination_round(&self) -> Vec<Pairing> {
+        let (unbeaten, once_beaten): (Vec<String>, Vec<String>) = self
+            .remaining(2)
+            .into_iter()
+            .partition(|entrant| self.losses(entrant) == 0);
+
+        if unbeaten.len() == 1 && once_beaten.len() == 1 {
+            return vec![pairing(vec![unbeaten[0].clone(), once_beaten[0].clone()])];
+        }
+
+        // A bracket down to a single entrant waits for the other bracket to catch up
+        let mut round = Vec::new();
+        if unbeaten.len() > 1 {
+            round.append(&mut pair_by_seed(&unbeaten));
+        }
+        if once_beaten.len() > 1 {
+            round.append(&mut pair_by_seed(&once_beaten));
+        }
+        round
+    }
+
+    // Whether or not a winner has been decided
+    pub fn is_finished(&self) -> bool {
+        match self.format {
+            Format::SingleElimination => self.remaining(1).len() <= 1,
+            Format::DoubleElimination => self.remaining(2).len() <= 1,
+            Format::RoundRobin => self.current_round().is_none(),
+        }
+    }
+
+    // The entrants from best to worst record
+    pub fn standings(&self) -> Vec<Standing> {
+        let losses_allowed = match self.format {
+            Format::SingleElimination => Some(1),
+            Format::DoubleElimination => Some(2),
+            Format::RoundRobin => None,
+        };
+
+        let mut standings: Vec<Standing> = self
+            .entrants
+            .iter()
+            .map(|entrant| {
+                let games: Vec<&Pairing> = self
+                    .rounds
+                    .iter()
+                    .flatten()
+                    .filter(|pairing| pairing.players.len() > 1 && pairing.winner.is_some())
+                    .filter(|pairing| pairing.players.iter().any(|player| player == entrant))
+                    .collect();
+                let wins = games
+                    .iter()
+                    .filter(|pairing| pairing.winner.as_ref() == Some(entrant))
+                    .count() as u32;
+                let losses = games.len() as u32 - wins;
+
+                Standing {
+                    name: entrant.clone(),
+                    played: games.len() as u32,
+                    wins: wins,
+                    losses: losses,
+                    eliminated: match losses_allowed {
+                        Some(losses_allowed) => losses >= losses_allowed,
+                        None => false,
+                    },
+                }
+            })
+            .collect();
+
+        standings.sort_by(|a, b| {
+            a.eliminated
+                .cmp(&b.eliminated)
+                .then(b.wins.cmp(&a.wins))
+                .then(a.losses.cmp(&b.losses))
+        });
+        standings
+    }
+
+    // Writes the tournament to a temporary file then renames it over the previous file so that
+    // tables reading the standings never see a partial file
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        write_json(self, path)
+    }
+
+    pub fn load(path: &str) -> Result<Tournament, String> {
+        match fs::read(path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(tournament) => Ok(tournament),
+                Err(e) => Err(format!("Invalid tournament {}; {}", path, e)),
+            },
+            Err(e) => Err(format!("Could not read tournament {}; {}", path, e)),
+        }
+    }
+}
+
+// Writes the value as JSON to a temporary file then renames it over the file at the given path
+fn write_json<T: Serialize>(value: &T, path: &str) -> Result<(), String> {
+    let bytes = match serde_json::to_vec_pretty(value) {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(format!("Could not serialize {}; {}", path, e)),
+    };
+
+    let temporary_path = format!("{}.tmp", path);
+    match fs::write(&temporary_path, bytes).and_then(|_| fs::rename(&temporary_path, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(format!("Could not save {}; {}", path, e)),
+    }
+}
+
+// Prints the standings of a tournament
+pub fn print_standings(tournament: &Tournament) {
+    for (place, standing) in tournament.standings().iter().enumerate() {
+        println!(
+            "{}. {} {}-{}{}",
+            place + 1,
+            standing.name,
+            standing.wins,
+            standing.losses,
+            if standing.eliminated {
+                " (eliminated)"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+// Runs the tournament at the given path to its end, opening a two player table for each undecided
+// pairing of the current round on consecutive ports from base_port. Each table is another instance
+// of the server given table_args, which are the TableConfig::from_args arguments after the number
+// of players; the seats of every table are reserved for the accounts in the pairing. The next
+// round starts once every table of the current round has written its result, and a round whose
+// results were written before the tournament was stopped is not played again
+// TODO Tables run as separate processes that each save the statistics store, so statistics
+// recorded by tables finishing at the same time may be lost
+pub fn run(path: &str, base_port: u16, table_args: &[String]) -> Result<(), String> {
+    let executable = match env::current_exe() {
+        Ok(executable) => executable,
+        Err(e) => return Err(format!("Could not find the server executable; {}", e)),
+    };
+
+    loop {
+        let mut tournament = Tournament::load(path)?;
+        let round = match tournament.current_round() {
+            Some(round) if !tournament.is_finished() => round,
+            _ => break,
+        };
+        println!("Starting round {}", round + 1);
+
+        // Open a table for every undecided pairing of the round
+        let mut tables = Vec::new();
+        for (index, pairing) in tournament.rounds[round].iter().enumerate() {
+            if pairing.winner.is_some() {
+                continue;
+            }
+
+            let port = base_port + index as u16;
+            let result_path = format!("{}.round{}.table{}.result", path, round + 1, index + 1);
+            println!(
+                "Table {} on port {}: {}",
+                index + 1,
+                port,
+                pairing.players.join(" vs ")
+            );
+
+            let table = if TableResult::load(&result_path).is_ok() {
+                None
+            } else {
+                let mut args = vec![port.to_string(), "2".to_string()];
+                args.extend_from_slice(table_args);
+                args.push("login=true".to_string());
+                args.push(format!("seats={}", pairing.players.join(",")));
+                args.push(format!("result={}", result_path));
+                args.push(format!("tournament={}", path));
+
+                match process::Command::new(&executable).args(&args).spawn() {
+                    Ok(child) => Some(child),
+                    Err(e) => return Err(format!("Could not open table {}; {}", index + 1, e)),
+                }
+            };
+            tables.push((index, result_path, table));
+        }
+
+        // Wait for every table to finish then record the results
+        for (index, result_path, table) in tables {
+            if let Some(mut child) = table {
+                if let Err(e) = child.wait() {
+                    return Err(format!("Table {} failed; {}", index + 1, e));
+                }
+            }
+
+            let result = TableResult::load(&result_path)?;
+            println!("{} won table {}", result.winner, index + 1);
+            tournament.record_result(round, index, &result.winner)?;
+            tournament.save(path)?;
+        }
+
+        print_standings(&tournament);
+    }
+
+    let tournament = Tournament::load(path)?;
+    if let Some(winner) = tournament.standings().first() {
+        println!("{} has won the tournament", winner.name);
+    }
+    Ok(())
+}
+
+// Makes a pairing of the given players; a single player has a bye and wins it immediately
+fn pairing(players: Vec<String>) -> Pairing {
+    Pairing {
+        winner: if players.len() == 1 {
+            Some(players[0].clone())
+        } else {
+            None
+        },
+        players: players,
+    }
+}
+
+// Pairs the highest seed with the lowest and so on inwards, so that elimination rounds are reseeded
+// every round; with an odd number of entrants the highest seed gets a bye
+fn pair_by_seed(entrants: &Vec<String>) -> Vec<Pairing> {
+    let mut round = Vec::new();
+    let mut rest = &entrants[..];
+    if rest.len() % 2 == 1 {
+        round.push(pairing(vec![rest[0].clone()]));
+        rest = &rest[1..];
+    }
+    for index in 0..rest.len() / 2 {
+        round.push(pairing(vec![
+            rest[index].clone(),
+            rest[rest.len() - 1 - index].clone(),
+        ]));
+    }
+    round
+}
+
+// Makes every round of a round robin with the circle method; the first entrant stays in place
+// while the others rotate around them, and with an odd number of entrants whoever would face the
+// empty seat gets a bye
+fn round_robin(entrants: &Vec<String>) -> Vec<Vec<Pairing>> {
+    let mut seats: Vec<Option<String>> = entrants.iter().cloned().map(Some).collect();
+    if seats.len() % 2 == 1 {
+        seats.push(None);
+    }
+    let count = seats.len();
+
+    let mut rounds = Vec::new();
+    for _ in 0..count - 1 {
+        let mut round = Vec::new();
+        for index in 0..count / 2 {
+            let players: Vec<String> = vec![seats[index].clone(), seats[count - 1 - index].clone()]
+                .into_iter()
+                .flatten()
+                .collect();
+            round.push(pairing(players));
+        }
+        rounds.push(round);
+
+        let last = seats.pop().unwrap();
+        seats.insert(1, last);
+    }
+    rounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &str) -> Vec<String> {
+        names
+            .split_whitespace()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn players(round: &Vec<Pairing>) -> Vec<Vec<String>> {
+        round
+            .iter()
+            .map(|pairing| pairing.players.clone())
+            .collect()
+    }
+
+    #[test]
+    fn highest_seed_plays_lowest_seed() {
+        let round = pair_by_seed(&names("a b c d"));
+        assert_eq!(players(&round), vec![names("a d"), names("b c")]);
+        assert!(round.iter().all(|pairing| pairing.winner.is_none()));
+    }
+
+    #[test]
+    fn highest_seed_gets_the_bye() {
+        let round = pair_by_seed(&names("a b c"));
+        assert_eq!(players(&round), vec![names("a"), names("b c")]);
+        assert_eq!(round[0].winner, Some("a".to_string()));
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once() {
+        for entrants in vec![names("a b c d"), names("a b c d e")] {
+            let rounds = round_robin(&entrants);
+            let count = entrants.len();
+            assert_eq!(rounds.len(), if count % 2 == 0 { count - 1 } else { count });
+
+            let mut games: Vec<Vec<String>> = rounds
+                .iter()
+                .flatten()
+                .filter(|pairing| pairing.players.len() == 2)
+                .map(|pairing| {
+                    let mut players = pairing.players.clone();
+                    players.sort();
+                    players
+                })
+                .collect();
+            games.sort();
+            let length = games.len();
+            games.dedup();
+            assert_eq!(games.len(), length);
+            assert_eq!(length, count * (count - 1) / 2);
+
+            // Nobody plays twice in a round
+            for round in &rounds {
+                let mut seated: Vec<&String> = round.iter().flat_map(|p| &p.players).collect();
+                seated.sort();
+                seated.dedup();
+                assert_eq!(seated.len(), count);
+            }
+        }
+    }
+
+    #[test]
+    fn single_elimination_reseeds_the_winners() {
+        let mut tournament = Tournament::new(Format::SingleElimination, names("a b c d")).unwrap();
+        tournament.record_result(0, 0, "d").unwrap();
+        assert_eq!(tournament.rounds.len(), 1);
+        tournament.record_result(0, 1, "B").unwrap();
+        assert_eq!(players(&tournament.rounds[1]), vec![names("b d")]);
+        tournament.record_result(1, 0, "d").unwrap();
+        assert!(tournament.is_finished());
+        assert_eq!(tournament.standings()[0].name, "d");
+    }
+
+    #[test]
+    fn double_elimination_ends_in_a_final_of_the_last_two() {
+        let mut tournament = Tournament::new(Format::DoubleElimination, names("a b c d")).unwrap();
+        tournament.record_result(0, 0, "a").unwrap();
+        tournament.record_result(0, 1, "b").unwrap();
+
+        // The unbeaten play each other and so do the once beaten
+        assert_eq!(
+            players(&tournament.rounds[1]),
+            vec![names("a b"), names("c d")]
+        );
+        tournament.record_result(1, 0, "a").unwrap();
+        tournament.record_result(1, 1, "c").unwrap();
+
+        // The once beaten bracket plays down to one entrant while the unbeaten entrant waits
+        assert_eq!(players(&tournament.rounds[2]), vec![names("b c")]);
+        tournament.record_result(2, 0, "c").unwrap();
+        assert_eq!(players(&tournament.rounds[3]), vec![names("a c")]);
+        tournament.record_result(3, 0, "c").unwrap();
+
+        // Both finalists have one loss, so a second final is played
+        assert!(!tournament.is_finished());
+        assert_eq!(players(&tournament.rounds[4]), vec![names("a c")]);
+        tournament.record_result(4, 0, "a").unwrap();
+        assert!(tournament.is_finished());
+    }
+
+    #[test]
+    fn pairing_can_only_be_decided_once_by_its_players() {
+        let mut tournament = Tournament::new(Format::RoundRobin, names("a b")).unwrap();
+        assert!(tournament.record_result(0, 0, "c").is_err());
+        tournament.record_result(0, 0, "a").unwrap();
+        assert!(tournament.record_result(0, 0, "b").is_err());
+        assert!(tournament.is_finished());
+    }
+}